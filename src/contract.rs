@@ -1,16 +1,36 @@
 use crate::error::ContractError;
-use crate::execute::create::{exec_create, handle_creation_reply};
+use crate::execute::code_ids::{
+    exec_allow_code_ids, exec_disallow_code_ids, exec_set_channel, exec_set_code_id_metadata, exec_set_default_code_id,
+};
+use crate::execute::create::{exec_create, exec_create_batch, handle_creation_reply};
+use crate::execute::creators::{
+    exec_remove_creator_policy, exec_set_creator_policy, exec_update_creator_allowlist, exec_update_creator_denylist,
+};
+use crate::execute::deregister::exec_deregister;
+use crate::execute::hide::{exec_hide, exec_unhide};
+use crate::execute::import::exec_import;
+use crate::execute::manager::{exec_accept_manager, exec_cancel_manager, exec_propose_manager};
 use crate::execute::migrate::{
-    exec_begin_migration, exec_cancel_migration, exec_migrate_one, exec_retry_migration, exec_step_migration,
-    handle_migration_reply,
+    exec_begin_migration, exec_cancel_migration, exec_migrate_one, exec_retry_migration, exec_rollback_migration,
+    exec_step_migration, handle_migration_reply, handle_rollback_reply,
 };
+use crate::execute::pause::{exec_pause, exec_unpause};
+use crate::execute::quotas::{exec_exempt_creator, exec_unexempt_creator};
+use crate::execute::receive::exec_receive;
+use crate::execute::roles::{exec_grant_role, exec_revoke_role};
+use crate::execute::set_index::{exec_remove_index, exec_set_index};
 use crate::execute::set_preset::{exec_remove_preset, exec_set_preset};
 use crate::execute::update::exec_update;
-use crate::execute::{set_config::exec_set_config, Context};
+use crate::execute::{
+    set_config::{exec_apply_pending_config, exec_cancel_pending_config, exec_set_config},
+    Context,
+};
 use crate::msg::{
-    ContractQueryMsg, ContractSetQueryMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, MigrationSessionMsg,
-    MigrationsExecuteMsg, MigrationsQueryMsg, PresetsExecuteMsg, PresetsQueryMsg, QueryMsg,
+    ContractQueryMsg, ContractSetQueryMsg, CreatorsExecuteMsg, CreatorsQueryMsg, ExecuteMsg, IndicesExecuteMsg,
+    IndicesQueryMsg, InstantiateMsg, ManagerExecuteMsg, MigrateMsg, MigrationSessionMsg, MigrationsExecuteMsg,
+    MigrationsQueryMsg, PresetsExecuteMsg, PresetsQueryMsg, QueryMsg, QuotasExecuteMsg, RolesExecuteMsg, RolesQueryMsg,
 };
+use crate::query::code_ids::{query_allowed_code_ids, query_channels, query_code_id};
 use crate::query::contract::has_tags::query_contract_has_tags;
 use crate::query::contract::is_related_to::query_contract_is_related_to;
 use crate::query::contract::metadata::query_contract_metadata;
@@ -19,12 +39,23 @@ use crate::query::contract::tags::query_contract_tags;
 use crate::query::contracts::in_range::query_contracts_in_range;
 use crate::query::contracts::related_to::query_contracts_related_to;
 use crate::query::contracts::with_tag::query_contracts_with_tag;
-use crate::query::migrations::query_migration_session;
+use crate::query::creator_quota::query_creator_quota;
+use crate::query::creators::{query_creator_allowlist, query_creator_denylist, query_creator_policies};
+use crate::query::indices::{query_index, query_paginated_indices};
+use crate::query::manager::query_pending_manager;
+use crate::query::migrations::{
+    query_contract_migration_results, query_historical_migration_session, query_migration_history,
+    query_migration_preview, query_migration_session, query_migration_session_results,
+};
+use crate::query::pending_config::query_pending_config;
+use crate::query::predict_address::query_predict_address;
 use crate::query::presets::{query_paginated_presets, query_preset};
+use crate::query::roles::{query_address_roles, query_role_members};
 use crate::query::{config::query_config, ReadonlyContext};
 use crate::state;
-use crate::state::storage::MIGRATION_REPLY_ID_2_STATE;
-use crate::util::ensure_is_manager;
+use crate::state::models::{PauseSwitch, Role};
+use crate::state::storage::{MIGRATION_REPLY_ID_2_STATE, MIGRATION_ROLLBACK_REPLY_ID_2_STATE};
+use crate::util::{ensure_has_role, ensure_is_manager, ensure_not_paused};
 use cosmwasm_std::{entry_point, to_json_binary as to_binary, Reply};
 use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response};
 use cw2::set_contract_version;
@@ -40,7 +71,7 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(state::init(Context { deps, env, info }, msg)?)
+    state::init(Context { deps, env, info }, msg)
 }
 
 #[entry_point]
@@ -52,11 +83,90 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     let ctx = Context { deps, env, info };
     match msg {
-        ExecuteMsg::Configure(config) => exec_set_config(ctx, config),
-        ExecuteMsg::Create(msg) => exec_create(ctx, msg),
-        ExecuteMsg::Update(msg) => exec_update(ctx, msg),
+        ExecuteMsg::Configure(config) => {
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::ConfigAdmin)?;
+            exec_set_config(ctx, config)
+        },
+        ExecuteMsg::ApplyPendingConfig {} => exec_apply_pending_config(ctx),
+        ExecuteMsg::AllowCodeIds {
+            code_ids,
+            execute_after,
+        } => {
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::ConfigAdmin)?;
+            exec_allow_code_ids(ctx, code_ids, execute_after)
+        },
+        ExecuteMsg::DisallowCodeIds {
+            code_ids,
+            execute_after,
+        } => {
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::ConfigAdmin)?;
+            exec_disallow_code_ids(ctx, code_ids, execute_after)
+        },
+        ExecuteMsg::SetDefaultCodeId { code_id, execute_after } => {
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::ConfigAdmin)?;
+            exec_set_default_code_id(ctx, code_id, execute_after)
+        },
+        ExecuteMsg::SetCodeIdMetadata { code_id, metadata } => {
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::ConfigAdmin)?;
+            exec_set_code_id_metadata(ctx, code_id, metadata)
+        },
+        ExecuteMsg::SetChannel {
+            name,
+            code_id,
+            execute_after,
+        } => {
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::ConfigAdmin)?;
+            exec_set_channel(ctx, name, code_id, execute_after)
+        },
+        ExecuteMsg::CancelPendingConfig {} => {
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::ConfigAdmin)?;
+            exec_cancel_pending_config(ctx)
+        },
+        ExecuteMsg::Create(msg) => {
+            ensure_not_paused(ctx.deps.storage, PauseSwitch::Create)?;
+            exec_create(ctx, msg, None)
+        },
+        ExecuteMsg::CreateBatch(msg) => {
+            ensure_not_paused(ctx.deps.storage, PauseSwitch::Create)?;
+            exec_create_batch(ctx, msg, None)
+        },
+        ExecuteMsg::Receive(msg) => {
+            ensure_not_paused(ctx.deps.storage, PauseSwitch::Create)?;
+            exec_receive(ctx, msg)
+        },
+        ExecuteMsg::Import(msg) => {
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::Indexer)?;
+            exec_import(ctx, msg)
+        },
+        ExecuteMsg::Update(msg) => {
+            if msg.contract.is_some() {
+                ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::Moderator)?;
+            } else {
+                ensure_not_paused(ctx.deps.storage, PauseSwitch::Update)?;
+            }
+            exec_update(ctx, msg)
+        },
+        ExecuteMsg::Hide { contract } => {
+            if contract.is_some() {
+                ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::Moderator)?;
+            }
+            exec_hide(ctx, contract)
+        },
+        ExecuteMsg::Unhide { contract } => {
+            if contract.is_some() {
+                ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::Moderator)?;
+            }
+            exec_unhide(ctx, contract)
+        },
+        ExecuteMsg::Deregister { contract, limit } => {
+            if contract.is_some() {
+                ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::Moderator)?;
+            }
+            exec_deregister(ctx, contract, limit)
+        },
         ExecuteMsg::Migrations(msg) => {
-            ensure_is_manager(ctx.deps.storage, &ctx.info.sender)?;
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::Migrator)?;
+            ensure_not_paused(ctx.deps.storage, PauseSwitch::Migrations)?;
             match msg {
                 MigrationsExecuteMsg::Migrate(params) => exec_migrate_one(ctx, params),
                 MigrationsExecuteMsg::Session(msg) => match msg {
@@ -64,16 +174,73 @@ pub fn execute(
                     MigrationSessionMsg::Step { name } => exec_step_migration(ctx, name),
                     MigrationSessionMsg::Cancel { name } => exec_cancel_migration(ctx, name),
                     MigrationSessionMsg::Retry { name, params } => exec_retry_migration(ctx, name, params),
+                    MigrationSessionMsg::Rollback {
+                        name,
+                        migrate_msg,
+                        migrate_msg_overrides,
+                        migrate_msg_template,
+                    } => exec_rollback_migration(ctx, name, migrate_msg, migrate_msg_overrides, migrate_msg_template),
                 },
             }
         },
         ExecuteMsg::Presets(msg) => {
-            ensure_is_manager(ctx.deps.storage, &ctx.info.sender)?;
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::PresetEditor)?;
+            ensure_not_paused(ctx.deps.storage, PauseSwitch::Presets)?;
             match msg {
                 PresetsExecuteMsg::Set(msg) => exec_set_preset(ctx, msg),
                 PresetsExecuteMsg::Remove { name } => exec_remove_preset(ctx, name),
             }
         },
+        ExecuteMsg::Indices(msg) => {
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::Indexer)?;
+            match msg {
+                IndicesExecuteMsg::Set(msg) => exec_set_index(ctx, msg),
+                IndicesExecuteMsg::Remove { name } => exec_remove_index(ctx, name),
+            }
+        },
+        ExecuteMsg::Quotas(msg) => {
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::ConfigAdmin)?;
+            match msg {
+                QuotasExecuteMsg::Exempt { creator } => exec_exempt_creator(ctx, creator),
+                QuotasExecuteMsg::Unexempt { creator } => exec_unexempt_creator(ctx, creator),
+            }
+        },
+        ExecuteMsg::Creators(msg) => {
+            ensure_has_role(ctx.deps.storage, &ctx.info.sender, Role::ConfigAdmin)?;
+            match msg {
+                CreatorsExecuteMsg::SetPolicy(msg) => exec_set_creator_policy(ctx, msg),
+                CreatorsExecuteMsg::RemovePolicy { scope } => exec_remove_creator_policy(ctx, scope),
+                CreatorsExecuteMsg::UpdateAllowlist { scope, add, remove } => {
+                    exec_update_creator_allowlist(ctx, scope, add, remove)
+                },
+                CreatorsExecuteMsg::UpdateDenylist { scope, add, remove } => {
+                    exec_update_creator_denylist(ctx, scope, add, remove)
+                },
+            }
+        },
+        ExecuteMsg::Roles(msg) => {
+            ensure_is_manager(ctx.deps.storage, &ctx.info.sender)?;
+            match msg {
+                RolesExecuteMsg::Grant { role, address } => exec_grant_role(ctx, role, address),
+                RolesExecuteMsg::Revoke { role, address } => exec_revoke_role(ctx, role, address),
+            }
+        },
+        ExecuteMsg::Manager(msg) => match msg {
+            ManagerExecuteMsg::Propose { address, expiry } => {
+                ensure_is_manager(ctx.deps.storage, &ctx.info.sender)?;
+                exec_propose_manager(ctx, address, expiry)
+            },
+            ManagerExecuteMsg::Accept {} => exec_accept_manager(ctx),
+            ManagerExecuteMsg::Cancel {} => exec_cancel_manager(ctx),
+        },
+        ExecuteMsg::Pause(pause) => {
+            ensure_is_manager(ctx.deps.storage, &ctx.info.sender)?;
+            exec_pause(ctx, pause)
+        },
+        ExecuteMsg::Unpause {} => {
+            ensure_is_manager(ctx.deps.storage, &ctx.info.sender)?;
+            exec_unpause(ctx)
+        },
     }
 }
 
@@ -84,7 +251,9 @@ pub fn reply(
     reply: Reply,
 ) -> Result<Response, ContractError> {
     if MIGRATION_REPLY_ID_2_STATE.has(deps.storage, reply.id) {
-        handle_migration_reply(deps, reply)
+        handle_migration_reply(deps, env, reply)
+    } else if MIGRATION_ROLLBACK_REPLY_ID_2_STATE.has(deps.storage, reply.id) {
+        handle_rollback_reply(deps, env, reply)
     } else {
         handle_creation_reply(deps, env, reply)
     }
@@ -99,8 +268,18 @@ pub fn query(
     let ctx = ReadonlyContext { deps, env };
     let result = match msg {
         QueryMsg::Config {} => to_binary(&query_config(ctx)?),
+        QueryMsg::PredictAddress(params) => to_binary(&query_predict_address(ctx, params)?),
+        QueryMsg::CreatorQuota { creator } => to_binary(&query_creator_quota(ctx, creator)?),
         QueryMsg::Migrations(msg) => match msg {
             MigrationsQueryMsg::Session(name) => to_binary(&query_migration_session(ctx, name)?),
+            MigrationsQueryMsg::History { cursor } => to_binary(&query_migration_history(ctx, cursor)?),
+            MigrationsQueryMsg::HistoricalSession { id } => to_binary(&query_historical_migration_session(ctx, id)?),
+            MigrationsQueryMsg::SessionResults { id, cursor } => {
+                to_binary(&query_migration_session_results(ctx, id, cursor)?)
+            },
+            MigrationsQueryMsg::ContractResults { address, cursor } => {
+                to_binary(&query_contract_migration_results(ctx, address, cursor)?)
+            },
         },
         QueryMsg::Contract(msg) => match msg {
             ContractQueryMsg::Metadata { address } => to_binary(&query_contract_metadata(ctx, address)?),
@@ -118,6 +297,25 @@ pub fn query(
             PresetsQueryMsg::Get { name } => to_binary(&query_preset(ctx, name)?),
             PresetsQueryMsg::Paginate { cursor } => to_binary(&query_paginated_presets(ctx, cursor)?),
         },
+        QueryMsg::Indices(msg) => match msg {
+            IndicesQueryMsg::Get { name } => to_binary(&query_index(ctx, name)?),
+            IndicesQueryMsg::Paginate { cursor } => to_binary(&query_paginated_indices(ctx, cursor)?),
+        },
+        QueryMsg::Creators(msg) => match msg {
+            CreatorsQueryMsg::Policies { cursor } => to_binary(&query_creator_policies(ctx, cursor)?),
+            CreatorsQueryMsg::Allowlist { scope, cursor } => to_binary(&query_creator_allowlist(ctx, scope, cursor)?),
+            CreatorsQueryMsg::Denylist { scope, cursor } => to_binary(&query_creator_denylist(ctx, scope, cursor)?),
+        },
+        QueryMsg::Roles(msg) => match msg {
+            RolesQueryMsg::Members { role, cursor } => to_binary(&query_role_members(ctx, role, cursor)?),
+            RolesQueryMsg::Of { address } => to_binary(&query_address_roles(ctx, address)?),
+        },
+        QueryMsg::PendingManager {} => to_binary(&query_pending_manager(ctx)?),
+        QueryMsg::PendingConfig {} => to_binary(&query_pending_config(ctx)?),
+        QueryMsg::AllowedCodeIds { cursor } => to_binary(&query_allowed_code_ids(ctx, cursor)?),
+        QueryMsg::CodeId { code_id } => to_binary(&query_code_id(ctx, code_id)?),
+        QueryMsg::Channels { cursor } => to_binary(&query_channels(ctx, cursor)?),
+        QueryMsg::PreviewMigration { params, cursor } => to_binary(&query_migration_preview(ctx, params, cursor)?),
     }?;
     Ok(result)
 }
@@ -126,8 +324,8 @@ pub fn query(
 pub fn migrate(
     deps: DepsMut,
     _env: Env,
-    _msg: MigrateMsg,
+    msg: MigrateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::default())
+    state::upgrade::upgrade(deps.storage, msg)
 }
@@ -12,6 +12,9 @@ pub enum ContractError {
     #[error("MigrationComplete: migration '{name}' already completed")]
     MigrationComplete { name: String },
 
+    #[error("MigrationRolledBack: migration '{name}' has been rolled back")]
+    MigrationRolledBack { name: String },
+
     #[error("NotAuthorized: {reason:?}")]
     NotAuthorized { reason: String },
 
@@ -0,0 +1,212 @@
+use crate::{
+    error::ContractError,
+    state::{
+        models::{CodeIdMetadata, ConfigChange},
+        storage::{CONFIG_ALLOWED_CODE_IDS, CONFIG_CHANNELS, CONFIG_DEFAULT_CODE_ID},
+    },
+    util::{allow_code_id, ensure_code_id_unreferenced},
+};
+use cosmwasm_std::{attr, Response, Storage, Timestamp, Uint64};
+
+use super::{
+    set_config::{enqueue_config_change, ensure_no_config_delay},
+    Context,
+};
+
+/// Each code ID patch operation is applied immediately or, given an
+/// execute-after time, enqueued as the pending config. Only the latter is
+/// possible once a config delay is set.
+pub fn exec_allow_code_ids(
+    ctx: Context,
+    code_ids: Vec<Uint64>,
+    execute_after: Option<Timestamp>,
+) -> Result<Response, ContractError> {
+    if let Some(execute_after) = execute_after {
+        let change = ConfigChange::AllowCodeIds { code_ids };
+        return enqueue_config_change(ctx, change, execute_after, "allow_code_ids");
+    }
+
+    ensure_no_config_delay(ctx.deps.storage)?;
+    allow_code_ids(ctx.deps.storage, &code_ids)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "allow_code_ids"),
+        attr("code_ids", join_code_ids(&code_ids)),
+    ]))
+}
+
+pub fn exec_disallow_code_ids(
+    ctx: Context,
+    code_ids: Vec<Uint64>,
+    execute_after: Option<Timestamp>,
+) -> Result<Response, ContractError> {
+    if let Some(execute_after) = execute_after {
+        let change = ConfigChange::DisallowCodeIds { code_ids };
+        return enqueue_config_change(ctx, change, execute_after, "disallow_code_ids");
+    }
+
+    ensure_no_config_delay(ctx.deps.storage)?;
+    disallow_code_ids(ctx.deps.storage, &code_ids)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "disallow_code_ids"),
+        attr("code_ids", join_code_ids(&code_ids)),
+    ]))
+}
+
+pub fn exec_set_default_code_id(
+    ctx: Context,
+    code_id: Option<Uint64>,
+    execute_after: Option<Timestamp>,
+) -> Result<Response, ContractError> {
+    if let Some(execute_after) = execute_after {
+        let change = ConfigChange::SetDefaultCodeId { code_id };
+        return enqueue_config_change(ctx, change, execute_after, "set_default_code_id");
+    }
+
+    ensure_no_config_delay(ctx.deps.storage)?;
+    set_default_code_id(ctx.deps.storage, code_id)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_default_code_id"),
+        attr("code_id", code_id.map(|x| x.to_string()).unwrap_or_default()),
+    ]))
+}
+
+/// Replace the registry metadata of an allowed code ID. A given checksum must
+/// match the checksum of the code stored on chain.
+pub fn exec_set_code_id_metadata(
+    ctx: Context,
+    code_id: Uint64,
+    metadata: CodeIdMetadata,
+) -> Result<Response, ContractError> {
+    let Context { deps, .. } = ctx;
+
+    if !CONFIG_ALLOWED_CODE_IDS.has(deps.storage, code_id.u64()) {
+        return Err(ContractError::ValidationError {
+            reason: format!("code ID not allowed: {}", code_id),
+        });
+    }
+
+    if let Some(version) = &metadata.version {
+        if !is_semver(version) {
+            return Err(ContractError::ValidationError {
+                reason: format!("invalid semantic version: {}", version),
+            });
+        }
+    }
+
+    if let Some(checksum) = &metadata.checksum {
+        let code_info = deps.querier.query_wasm_code_info(code_id.u64())?;
+        if code_info.checksum != *checksum {
+            return Err(ContractError::ValidationError {
+                reason: format!("checksum doesn't match code ID {}: {}", code_id, code_info.checksum),
+            });
+        }
+    }
+
+    CONFIG_ALLOWED_CODE_IDS.save(deps.storage, code_id.u64(), &metadata)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_code_id_metadata"),
+        attr("code_id", code_id.to_string()),
+    ]))
+}
+
+pub fn exec_set_channel(
+    ctx: Context,
+    name: String,
+    code_id: Option<Uint64>,
+    execute_after: Option<Timestamp>,
+) -> Result<Response, ContractError> {
+    if let Some(execute_after) = execute_after {
+        let change = ConfigChange::SetChannel { name, code_id };
+        return enqueue_config_change(ctx, change, execute_after, "set_channel");
+    }
+
+    ensure_no_config_delay(ctx.deps.storage)?;
+    set_channel(ctx.deps.storage, &name, code_id)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_channel"),
+        attr("channel", name),
+        attr("code_id", code_id.map(|x| x.to_string()).unwrap_or_default()),
+    ]))
+}
+
+pub fn allow_code_ids(
+    store: &mut dyn Storage,
+    code_ids: &[Uint64],
+) -> Result<(), ContractError> {
+    for code_id in code_ids.iter() {
+        allow_code_id(store, *code_id)?;
+    }
+    Ok(())
+}
+
+pub fn disallow_code_ids(
+    store: &mut dyn Storage,
+    code_ids: &[Uint64],
+) -> Result<(), ContractError> {
+    for code_id in code_ids.iter() {
+        ensure_code_id_unreferenced(store, *code_id)?;
+        CONFIG_ALLOWED_CODE_IDS.remove(store, code_id.u64());
+    }
+    Ok(())
+}
+
+/// Set or remove the default code ID, allowing it if it isn't already.
+pub fn set_default_code_id(
+    store: &mut dyn Storage,
+    code_id: Option<Uint64>,
+) -> Result<(), ContractError> {
+    if let Some(code_id) = code_id {
+        allow_code_id(store, code_id)?;
+        CONFIG_DEFAULT_CODE_ID.save(store, &code_id)?;
+    } else {
+        CONFIG_DEFAULT_CODE_ID.remove(store);
+    }
+    Ok(())
+}
+
+/// Point a release channel at an allowed code ID, or remove the channel.
+pub fn set_channel(
+    store: &mut dyn Storage,
+    name: &String,
+    code_id: Option<Uint64>,
+) -> Result<(), ContractError> {
+    if let Some(code_id) = code_id {
+        if name.is_empty() {
+            return Err(ContractError::ValidationError {
+                reason: "channel name must be non-empty".to_owned(),
+            });
+        }
+        if !CONFIG_ALLOWED_CODE_IDS.has(store, code_id.u64()) {
+            return Err(ContractError::ValidationError {
+                reason: format!("code ID not allowed: {}", code_id),
+            });
+        }
+        CONFIG_CHANNELS.save(store, name, &code_id)?;
+    } else {
+        CONFIG_CHANNELS.remove(store, name);
+    }
+    Ok(())
+}
+
+fn join_code_ids(code_ids: &[Uint64]) -> String {
+    code_ids
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Check for MAJOR.MINOR.PATCH with an optional pre-release or build suffix.
+fn is_semver(version: &str) -> bool {
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<&str> = core.split('.').collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|x| !x.is_empty() && x.chars().all(|c| c.is_ascii_digit()))
+}
@@ -1,51 +1,191 @@
 use crate::{
     error::ContractError,
     math::{add_u32, add_u64},
-    msg::{CreateMsg, IndexValue},
+    msg::{BatchCreationPolicy, CreateBatchMsg, CreateMsg, FactoryCallbackMsg, IndexValue},
     state::{
-        models::SubMsgContext,
+        models::{CreationBatch, CreationFee, SubMsgContext},
         storage::{
-            CONFIG_ALLOWED_CODE_IDS, CONFIG_DEFAULT_CODE_ID, CONTRACT_ADDR_2_ID, CONTRACT_COUNTER, CONTRACT_ID_2_ADDR,
-            CONTRACT_ID_2_NAME, CONTRACT_ID_COUNTER, CONTRACT_NAME_2_ID, ID_2_CODE_ID, IX_ADMIN, IX_CODE_ID,
-            IX_CREATED_AT, IX_CREATED_BY, IX_UPDATED_AT, REPLY_ID_COUNTER, SUBMSG_CONTEXTS,
+            CONFIG_CODE_ID_FEES, CONFIG_PRESET_FEES, CONFIG_TREASURY, CONTRACT_ADDR_2_ID, CONTRACT_COUNTER,
+            CONTRACT_ID_2_ADDR, CONTRACT_ID_2_LABEL, CONTRACT_ID_2_NAME, CONTRACT_ID_COUNTER, CONTRACT_NAME_2_ID,
+            ID_2_ADMIN, ID_2_CODE_ID, ID_2_CREATED_AT, ID_2_CREATED_BY, ID_2_UPDATED_AT, IX_ADMIN, IX_CODE_ID,
+            IX_CREATED_AT, IX_CREATED_BY, IX_UPDATED_AT, MANAGED_BY, REPLY_ID_COUNTER, SUBMSG_BATCHES, SUBMSG_CONTEXTS,
         },
     },
-    util::apply_preset,
+    util::{apply_preset, resolve_code_id, validate_salt},
 };
-use cosmwasm_std::{attr, Addr, DepsMut, Env, Event, Reply, Response, StdError, SubMsg, WasmMsg};
+use cosmwasm_std::{
+    attr, to_json_binary, Addr, BankMsg, Coin, Coins, CosmosMsg, DepsMut, Env, Event, Reply, ReplyOn, Response,
+    StdError, Storage, SubMsg, SubMsgResult, Timestamp, Uint128, WasmMsg,
+};
+use cw20::{Cw20CoinVerified, Cw20ExecuteMsg, Denom};
 use cw_utils::{parse_reply_instantiate_data, MsgInstantiateContractResponse};
 
-use super::Context;
+use super::{
+    creators::ensure_creator_allowed,
+    quotas::{consume_creator_quota, release_creator_quota},
+    update::{apply_updates, validate_updates},
+    Context,
+};
+
+const MAX_BATCH_SIZE: usize = 100;
 
 /// Instantiate a new contract through the factory, adding it to its internal
 /// data structures and indices via the SubMsg reply.
 pub fn exec_create(
     ctx: Context,
     msg: CreateMsg,
+    cw20: Option<Cw20CoinVerified>,
+) -> Result<Response, ContractError> {
+    let Context { mut deps, env, info } = ctx;
+    let mut cw20 = cw20;
+    let mut native = to_coins(&info.funds)?;
+
+    consume_creator_quota(deps.storage, env.block.time, &info.sender, 1)?;
+
+    // Split the creation fee off before forwarding what remains
+    let fee = resolve_creation_fee(deps.storage, &msg)?;
+    deduct_fees(fee.as_slice(), &mut native, &mut cw20)?;
+
+    let funds = if let Some(funds) = msg.funds.to_owned() {
+        deduct_funds(&mut native, &funds)?;
+        funds
+    } else {
+        std::mem::take(&mut native).into_vec()
+    };
+
+    let msgs = refund(&info.sender, native, cw20)?;
+
+    let payment = CreationPayment { fee, funds };
+    let submsg = build_creation_submsg(&mut deps, &env, &info.sender, msg, payment, None, ReplyOn::Success)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "create")])
+        .add_submessage(submsg)
+        .add_messages(msgs))
+}
+
+/// Instantiate several contracts in one transaction. Each contract is created
+/// by its own SubMsg with its own reply context. Under the best-effort policy,
+/// failing to create one contract doesn't prevent creating the others. Once
+/// every reply has been handled, a summary event lists the created addresses
+/// in the order given.
+pub fn exec_create_batch(
+    ctx: Context,
+    msg: CreateBatchMsg,
+    cw20: Option<Cw20CoinVerified>,
 ) -> Result<Response, ContractError> {
-    let Context { deps, env, info } = ctx;
+    let Context { mut deps, env, info } = ctx;
+    let mut cw20 = cw20;
+    let mut native = to_coins(&info.funds)?;
+    let CreateBatchMsg { contracts, policy } = msg;
 
+    if contracts.is_empty() || contracts.len() > MAX_BATCH_SIZE {
+        return Err(ContractError::ValidationError {
+            reason: format!("batch must contain 1 to {} contracts", MAX_BATCH_SIZE),
+        });
+    }
+
+    // Names are only checked against registered contracts, so they must also
+    // be unique within the batch.
+    let mut names: Vec<&String> = Vec::with_capacity(contracts.len());
+    for name in contracts.iter().filter_map(|x| x.name.as_ref()) {
+        if names.contains(&name) {
+            return Err(ContractError::ValidationError {
+                reason: format!("contract name used more than once in batch: {}", name),
+            });
+        }
+        names.push(name);
+    }
+
+    consume_creator_quota(deps.storage, env.block.time, &info.sender, contracts.len() as u32)?;
+
+    // Creation fees are due for each contract in the batch
+    let fees = contracts
+        .iter()
+        .map(|msg| resolve_creation_fee(deps.storage, msg))
+        .collect::<Result<Vec<Option<CreationFee>>, _>>()?;
+    deduct_fees(
+        &fees.iter().flatten().cloned().collect::<Vec<CreationFee>>(),
+        &mut native,
+        &mut cw20,
+    )?;
+
+    // Funds sent beyond the fees are split across the contracts in the batch
+    let funds: Vec<Vec<Coin>> = contracts
+        .iter()
+        .map(|x| x.funds.to_owned().unwrap_or_default())
+        .collect();
+    for funds in funds.iter() {
+        deduct_funds(&mut native, funds)?;
+    }
+
+    let msgs = refund(&info.sender, native, cw20)?;
+
+    let best_effort = matches!(policy, Some(BatchCreationPolicy::BestEffort));
+    let reply_on = if best_effort { ReplyOn::Always } else { ReplyOn::Success };
+
+    // The batch is identified by the next reply ID, which is its first SubMsg's
+    let batch_id = REPLY_ID_COUNTER.load(deps.storage)?.u64();
+
+    SUBMSG_BATCHES.save(
+        deps.storage,
+        batch_id,
+        &CreationBatch {
+            size: contracts.len() as u32,
+            addresses: Vec::with_capacity(contracts.len()),
+            best_effort,
+        },
+    )?;
+
+    let mut submsgs: Vec<SubMsg> = Vec::with_capacity(contracts.len());
+    for ((msg, fee), funds) in contracts.into_iter().zip(fees).zip(funds) {
+        submsgs.push(build_creation_submsg(
+            &mut deps,
+            &env,
+            &info.sender,
+            msg,
+            CreationPayment { fee, funds },
+            Some(batch_id),
+            reply_on.to_owned(),
+        )?);
+    }
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "create_batch"),
+            attr("batch_id", batch_id.to_string()),
+        ])
+        .add_submessages(submsgs)
+        .add_messages(msgs))
+}
+
+/// What was paid for creating one contract, which is held by the factory until
+/// its reply, when the fee goes to the treasury or everything is refunded.
+struct CreationPayment {
+    fee: Option<CreationFee>,
+    funds: Vec<Coin>,
+}
+
+/// Allocate a contract ID and reply context for a new contract and build the
+/// SubMsg that instantiates it.
+fn build_creation_submsg(
+    deps: &mut DepsMut,
+    env: &Env,
+    sender: &Addr,
+    msg: CreateMsg,
+    payment: CreationPayment,
+    batch_id: Option<u64>,
+    reply_on: ReplyOn,
+) -> Result<SubMsg, ContractError> {
     let reply_id = REPLY_ID_COUNTER
         .update(deps.storage, |n| -> Result<_, ContractError> { add_u64(n, 1u64) })?
         .u64()
         - 1;
 
-    let code_id = if let Some(code_id) = msg.code_id {
-        if CONFIG_ALLOWED_CODE_IDS.has(deps.storage, code_id.into()) {
-            code_id
-        } else {
-            return Err(ContractError::NotAuthorized {
-                reason: format!("not allowed to instantiate code ID: {}", code_id.u64()),
-            });
-        }
-    } else {
-        CONFIG_DEFAULT_CODE_ID
-            .load(deps.storage)
-            .map_err(|_| ContractError::ValidationError {
-                reason: "no default code ID set in factory".to_owned(),
-            })?
-    }
-    .u64();
+    let CreationPayment { fee, funds } = payment;
+    let code_id = resolve_code_id(deps.storage, msg.code_id, msg.channel.as_ref())?.u64();
+
+    ensure_creator_allowed(deps.storage, sender, code_id.into(), msg.preset.as_ref())?;
 
     // NOTE: By default, the factory is the admin of the contracts instantiated
     // through it. This is in order to be able to exec admin functions via the
@@ -53,9 +193,19 @@ pub fn exec_create(
     let admin = if let Some(admin) = msg.admin {
         deps.api.addr_validate(admin.as_str())?
     } else {
-        env.contract.address
+        env.contract.address.to_owned()
     };
 
+    // Validate initial custom index values, tags and relations now, rather
+    // than failing in the reply.
+    let indices = msg.indices.unwrap_or_default();
+    let is_manager = MANAGED_BY.load(deps.storage)? == sender;
+    let relations = msg.relations.unwrap_or_default();
+    let tags = msg.tags.unwrap_or_default();
+    validate_updates(deps.storage, is_manager, &indices, &tags, &relations)?;
+
+    ensure_name_available(deps.storage, msg.name.as_ref())?;
+
     // Generate contract ID
     let contract_id = CONTRACT_ID_COUNTER.update(deps.storage, |n| -> Result<_, ContractError> { add_u32(n, 1) })? - 1;
 
@@ -65,86 +215,510 @@ pub fn exec_create(
         reply_id,
         &SubMsgContext {
             code_id: code_id.into(),
-            created_by: info.sender.to_owned(),
-            admin: admin.to_owned(),
+            created_by: sender.to_owned(),
+            admin: Some(admin.to_owned()),
             name: msg.name,
+            label: Some(msg.label.to_owned()),
             contract_id,
+            batch_id,
+            indices,
+            relations,
+            tags,
+            callback: msg.callback.unwrap_or(false),
+            fee,
+            funds: funds.to_owned(),
         },
     )?;
 
-    Ok(Response::new()
-        .add_attributes(vec![attr("action", "create")])
-        .add_submessage(SubMsg::reply_on_success(
-            WasmMsg::Instantiate {
-                msg: apply_preset(deps.storage, msg.instantiate_msg, msg.preset)?,
-                funds: info.funds.to_owned(),
-                label: msg.label,
-                admin: Some(admin.into()),
-                code_id,
+    let instantiate_msg = apply_preset(deps.storage, msg.instantiate_msg, msg.preset)?;
+
+    // Use instantiate2 when given a salt, so that the address of the new
+    // contract is known in advance. See the PredictAddress query.
+    let wasm_msg = if let Some(salt) = msg.salt {
+        validate_salt(&salt)?;
+        WasmMsg::Instantiate2 {
+            msg: instantiate_msg,
+            label: msg.label,
+            admin: Some(admin.into()),
+            code_id,
+            funds,
+            salt,
+        }
+    } else {
+        WasmMsg::Instantiate {
+            msg: instantiate_msg,
+            label: msg.label,
+            admin: Some(admin.into()),
+            code_id,
+            funds,
+        }
+    };
+
+    Ok(SubMsg {
+        id: reply_id,
+        msg: wasm_msg.into(),
+        gas_limit: None,
+        reply_on,
+    })
+}
+
+/// Get the fee for creating a contract, preferring the fee of its preset over
+/// that of its code ID.
+fn resolve_creation_fee(
+    store: &dyn Storage,
+    msg: &CreateMsg,
+) -> Result<Option<CreationFee>, ContractError> {
+    if let Some(preset) = &msg.preset {
+        if let Some(fee) = CONFIG_PRESET_FEES.may_load(store, preset)? {
+            return Ok(Some(fee));
+        }
+    }
+    let code_id = resolve_code_id(store, msg.code_id, msg.channel.as_ref())?;
+    Ok(CONFIG_CODE_ID_FEES.may_load(store, code_id.u64())?)
+}
+
+/// Deduct creation fees from the payment received, erroring if it's
+/// insufficient. The fees are held until each contract's reply.
+fn deduct_fees(
+    fees: &[CreationFee],
+    native: &mut Coins,
+    cw20: &mut Option<Cw20CoinVerified>,
+) -> Result<(), ContractError> {
+    // CW20 tokens are only accepted as payment of a creation fee
+    if let Some(received) = cw20.as_ref() {
+        if !fees
+            .iter()
+            .any(|x| matches!(&x.denom, Denom::Cw20(token) if *token == received.address))
+        {
+            return Err(ContractError::ValidationError {
+                reason: format!("no creation fee is paid in CW20 token: {}", received.address),
+            });
+        }
+    }
+
+    for CreationFee { denom, amount } in fees.iter() {
+        match denom {
+            Denom::Native(denom) => {
+                deduct_funds(native, &[Coin::new(amount.u128(), denom)])?;
+            },
+            Denom::Cw20(token) => {
+                let received =
+                    cw20.as_mut()
+                        .filter(|x| x.address == *token)
+                        .ok_or_else(|| ContractError::ValidationError {
+                            reason: format!("creation fee must be paid in CW20 token: {}", token),
+                        })?;
+                received.amount = received
+                    .amount
+                    .checked_sub(*amount)
+                    .map_err(|_| ContractError::ValidationError {
+                        reason: format!("insufficient {} tokens sent for creation fee", token),
+                    })?;
             },
-            reply_id,
-        )))
+        }
+    }
+
+    Ok(())
+}
+
+/// Build the message that sends a creation fee to the treasury.
+fn pay_fee(
+    store: &dyn Storage,
+    fee: &CreationFee,
+) -> Result<CosmosMsg, ContractError> {
+    let treasury = match CONFIG_TREASURY.may_load(store)? {
+        Some(treasury) => treasury,
+        None => MANAGED_BY.load(store)?,
+    };
+    match &fee.denom {
+        Denom::Native(denom) => Ok(BankMsg::Send {
+            to_address: treasury.to_string(),
+            amount: vec![Coin::new(fee.amount.u128(), denom)],
+        }
+        .into()),
+        Denom::Cw20(token) => build_cw20_transfer(token, &treasury, fee.amount),
+    }
+}
+
+/// Give the creator of a contract that couldn't be created back its quota and
+/// fee, as well as the funds meant for the contract if they weren't sent.
+fn refund_failed_creation(
+    store: &mut dyn Storage,
+    time: Timestamp,
+    context: &SubMsgContext,
+    include_funds: bool,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    release_creator_quota(store, time, &context.created_by, 1)?;
+
+    let mut native = if include_funds {
+        to_coins(&context.funds)?
+    } else {
+        Coins::default()
+    };
+    let mut cw20: Option<Cw20CoinVerified> = None;
+
+    match &context.fee {
+        Some(CreationFee {
+            denom: Denom::Native(denom),
+            amount,
+        }) => native.add(Coin::new(amount.u128(), denom))?,
+        Some(CreationFee {
+            denom: Denom::Cw20(token),
+            amount,
+        }) => {
+            cw20 = Some(Cw20CoinVerified {
+                address: token.to_owned(),
+                amount: *amount,
+            })
+        },
+        None => {},
+    }
+
+    refund(&context.created_by, native, cw20)
+}
+
+/// Deduct funds from the payment received, erroring if it's insufficient.
+fn deduct_funds(
+    native: &mut Coins,
+    funds: &[Coin],
+) -> Result<(), ContractError> {
+    for coin in funds.iter() {
+        native
+            .sub(coin.to_owned())
+            .map_err(|_| ContractError::ValidationError {
+                reason: format!("insufficient funds sent: {} required", coin),
+            })?;
+    }
+    Ok(())
+}
+
+/// Build messages that return any overpayment to the sender.
+fn refund(
+    sender: &Addr,
+    native: Coins,
+    cw20: Option<Cw20CoinVerified>,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if !native.is_empty() {
+        msgs.push(
+            BankMsg::Send {
+                to_address: sender.to_string(),
+                amount: native.into_vec(),
+            }
+            .into(),
+        );
+    }
+    if let Some(Cw20CoinVerified { address, amount }) = cw20.filter(|x| !x.amount.is_zero()) {
+        msgs.push(build_cw20_transfer(&address, sender, amount)?);
+    }
+    Ok(msgs)
+}
+
+fn build_cw20_transfer(
+    token: &Addr,
+    recipient: &Addr,
+    amount: Uint128,
+) -> Result<CosmosMsg, ContractError> {
+    Ok(WasmMsg::Execute {
+        contract_addr: token.to_string(),
+        msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    }
+    .into())
+}
+
+fn to_coins(funds: &[Coin]) -> Result<Coins, ContractError> {
+    Coins::try_from(funds.to_vec()).map_err(|e| ContractError::ValidationError { reason: e.to_string() })
 }
 
 /// Extract and save created contract address and initialize indexes and other
-/// contract-related state data structures.
+/// contract-related state data structures. Failures are only ever received
+/// here for best-effort batches.
 pub fn handle_creation_reply(
     deps: DepsMut,
     env: Env,
     reply: Reply,
 ) -> Result<Response, ContractError> {
-    let resp = Response::new();
-
-    // Extract created contract address
-    let MsgInstantiateContractResponse { contract_address, .. } = parse_reply_instantiate_data(reply.to_owned())
-        .map_err(|e| {
-            ContractError::Std(StdError::GenericErr {
-                msg: format!(
-                    "failed to extract newly created contract address from reply: {}",
-                    e.to_string()
-                ),
-            })
-        })?;
+    let mut resp = Response::new();
+
+    let context = SUBMSG_CONTEXTS.load(deps.storage, reply.id)?;
+
+    SUBMSG_CONTEXTS.remove(deps.storage, reply.id);
+
+    let created_address = match &reply.result {
+        SubMsgResult::Ok(_) => {
+            // Extract created contract address
+            let MsgInstantiateContractResponse { contract_address, .. } =
+                parse_reply_instantiate_data(reply).map_err(|e| {
+                    ContractError::Std(StdError::GenericErr {
+                        msg: format!("failed to extract newly created contract address from reply: {}", e),
+                    })
+                })?;
+
+            let contract_address = Addr::unchecked(contract_address);
+            let is_manager = MANAGED_BY.load(deps.storage)? == context.created_by;
+
+            // Things may have changed since the SubMsg was built, e.g. another
+            // contract in the batch took the name. Under the best-effort
+            // policy, this only fails this contract, which has been created
+            // with its funds but isn't registered, so only its fee is refunded.
+            if let Err(e) = ensure_registrable(deps.storage, &context, is_manager) {
+                if !is_best_effort(deps.storage, context.batch_id)? {
+                    return Err(e);
+                }
+                resp = resp
+                    .add_messages(refund_failed_creation(deps.storage, env.block.time, &context, false)?)
+                    .add_event(Event::new("factory-create-error").add_attributes(vec![
+                        attr("contract_address", contract_address.to_string()),
+                        attr("code_id", context.code_id.to_string()),
+                        attr("label", context.label.to_owned().unwrap_or_default()),
+                        attr("error", e.to_string()),
+                    ]));
+                None
+            } else {
+                register_contract(deps.storage, env.block.time, &contract_address, &context)?;
+
+                // Apply initial custom index values, tags and relations, as if the
+                // new contract had sent an update to the factory.
+                apply_updates(
+                    deps.storage,
+                    context.contract_id,
+                    is_manager,
+                    &context.indices,
+                    &context.tags,
+                    &context.relations,
+                )?;
+
+                resp = resp.add_event(Event::new("factory-create").add_attributes(vec![
+                    attr("contract_address", contract_address.to_string()),
+                    attr("code_id", context.code_id.to_string()),
+                    attr(
+                        "admin",
+                        context.admin.as_ref().map(|a| a.to_string()).unwrap_or_default(),
+                    ),
+                ]));
+
+                if context.callback {
+                    resp = resp.add_message(WasmMsg::Execute {
+                        contract_addr: context.created_by.to_string(),
+                        msg: to_json_binary(&FactoryCallbackMsg::Created {
+                            address: contract_address.to_owned(),
+                            contract_id: context.contract_id,
+                            code_id: context.code_id,
+                            name: context.name.to_owned(),
+                        })?,
+                        funds: vec![],
+                    });
+                }
+
+                if let Some(fee) = &context.fee {
+                    resp = resp.add_message(pay_fee(deps.storage, fee)?);
+                }
+
+                // Batches set the response data once all contracts are created
+                if context.batch_id.is_none() {
+                    resp = resp.set_data(to_json_binary(&contract_address)?);
+                }
+
+                Some(contract_address)
+            }
+        },
+        SubMsgResult::Err(e) => {
+            resp = resp
+                .add_messages(refund_failed_creation(deps.storage, env.block.time, &context, true)?)
+                .add_event(Event::new("factory-create-error").add_attributes(vec![
+                    attr("code_id", context.code_id.to_string()),
+                    attr("label", context.label.to_owned().unwrap_or_default()),
+                    attr("error", e.to_owned()),
+                ]));
+            None
+        },
+    };
+
+    if let Some(batch_id) = context.batch_id {
+        resp = update_creation_batch(deps.storage, batch_id, created_address, resp)?;
+    }
+
+    Ok(resp)
+}
+
+/// Record the outcome of one contract in a batch, emitting the batch summary
+/// event and setting the response data to the batch's addresses after its last
+/// contract.
+fn update_creation_batch(
+    store: &mut dyn Storage,
+    batch_id: u64,
+    created_address: Option<Addr>,
+    resp: Response,
+) -> Result<Response, ContractError> {
+    let mut batch = SUBMSG_BATCHES.load(store, batch_id)?;
+
+    batch.addresses.push(created_address);
+
+    if batch.addresses.len() < batch.size as usize {
+        SUBMSG_BATCHES.save(store, batch_id, &batch)?;
+        return Ok(resp);
+    }
+
+    SUBMSG_BATCHES.remove(store, batch_id);
+
+    let n_created = batch.addresses.iter().flatten().count();
+    let addresses: Vec<String> = batch
+        .addresses
+        .iter()
+        .map(|x| x.as_ref().map(|a| a.to_string()).unwrap_or_default())
+        .collect();
+
+    Ok(resp
+        .set_data(to_json_binary(&batch.addresses)?)
+        .add_event(Event::new("factory-create-batch").add_attributes(vec![
+            attr("batch_id", batch_id.to_string()),
+            attr("addresses", addresses.join(",")),
+            attr("n_created", n_created.to_string()),
+            attr("n_failed", (addresses.len() - n_created).to_string()),
+        ])))
+}
+
+/// Add a contract to the factory's lookup tables and built-in indices. This is
+/// the state shared by contracts created through the factory and imported ones.
+/// Check that registering a created contract won't fail, before anything is
+/// written for it.
+fn ensure_registrable(
+    store: &dyn Storage,
+    context: &SubMsgContext,
+    is_manager: bool,
+) -> Result<(), ContractError> {
+    ensure_name_available(store, context.name.as_ref())?;
+    validate_updates(store, is_manager, &context.indices, &context.tags, &context.relations)
+}
+
+fn is_best_effort(
+    store: &dyn Storage,
+    batch_id: Option<u64>,
+) -> Result<bool, ContractError> {
+    Ok(match batch_id {
+        Some(batch_id) => SUBMSG_BATCHES.load(store, batch_id)?.best_effort,
+        None => false,
+    })
+}
 
-    let t = env.block.time.nanos();
-    let contract_address = Addr::unchecked(contract_address);
+fn ensure_name_available(
+    store: &dyn Storage,
+    name: Option<&String>,
+) -> Result<(), ContractError> {
+    if let Some(name) = name {
+        if CONTRACT_NAME_2_ID.has(store, name) {
+            return Err(ContractError::ValidationError {
+                reason: format!("contract name already in use: {}", name),
+            });
+        }
+    }
+    Ok(())
+}
 
+pub fn register_contract(
+    store: &mut dyn Storage,
+    time: Timestamp,
+    contract_address: &Addr,
+    context: &SubMsgContext,
+) -> Result<(), ContractError> {
     let SubMsgContext {
         contract_id,
         code_id,
         created_by,
         admin,
         name,
-    } = SUBMSG_CONTEXTS.load(deps.storage, reply.id)?;
+        label,
+        ..
+    } = context;
 
-    SUBMSG_CONTEXTS.remove(deps.storage, reply.id);
+    let contract_id = *contract_id;
 
-    CONTRACT_COUNTER.update(deps.storage, |n| -> Result<_, ContractError> { add_u32(n, 1) })?;
+    ensure_name_available(store, name.as_ref())?;
 
-    CONTRACT_ADDR_2_ID.save(deps.storage, &contract_address, &contract_id)?;
-    CONTRACT_ID_2_ADDR.save(deps.storage, contract_id, &contract_address)?;
+    CONTRACT_COUNTER.update(store, |n| -> Result<_, ContractError> { add_u32(n, 1) })?;
 
-    let code_id_bytes = IndexValue::Uint64(code_id).to_bytes();
-    let created_at_bytes = IndexValue::Uint64(t.into()).to_bytes();
+    CONTRACT_ADDR_2_ID.save(store, contract_address, &contract_id)?;
+    CONTRACT_ID_2_ADDR.save(store, contract_id, contract_address)?;
+
+    let code_id_bytes = IndexValue::Uint64(*code_id).to_bytes();
+    let created_at_bytes = IndexValue::Uint64(time.nanos().into()).to_bytes();
     let created_by_bytes = IndexValue::String(created_by.into()).to_bytes();
-    let admin_bytes = IndexValue::String(admin.clone().into()).to_bytes();
 
-    ID_2_CODE_ID.save(deps.storage, contract_id, &code_id_bytes)?;
+    // Reverse lookup tables, used to resolve each contract's metadata and to
+    // remove stale entries from indices when they change.
+    ID_2_CODE_ID.save(store, contract_id, &code_id_bytes)?;
+    ID_2_CREATED_AT.save(store, contract_id, &created_at_bytes)?;
+    ID_2_UPDATED_AT.save(store, contract_id, &created_at_bytes)?;
+    ID_2_CREATED_BY.save(store, contract_id, &created_by_bytes)?;
+
+    if let Some(contract_label) = label {
+        CONTRACT_ID_2_LABEL.save(store, contract_id, contract_label)?;
+    }
 
-    if let Some(contract_name) = &name {
-        CONTRACT_NAME_2_ID.save(deps.storage, contract_name, &contract_id)?;
-        CONTRACT_ID_2_NAME.save(deps.storage, contract_id, contract_name)?;
+    if let Some(contract_name) = name {
+        CONTRACT_NAME_2_ID.save(store, contract_name, &contract_id)?;
+        CONTRACT_ID_2_NAME.save(store, contract_id, contract_name)?;
     }
 
-    IX_CODE_ID.save(deps.storage, (&code_id_bytes, contract_id), &0)?;
-    IX_CREATED_BY.save(deps.storage, (&created_by_bytes, contract_id), &0)?;
-    IX_CREATED_AT.save(deps.storage, (&created_at_bytes, contract_id), &0)?;
-    IX_UPDATED_AT.save(deps.storage, (&created_at_bytes, contract_id), &0)?;
-    IX_ADMIN.save(deps.storage, (&admin_bytes, contract_id), &0)?;
+    IX_CODE_ID.save(store, (&code_id_bytes, contract_id), &0)?;
+    IX_CREATED_BY.save(store, (&created_by_bytes, contract_id), &0)?;
+    IX_CREATED_AT.save(store, (&created_at_bytes, contract_id), &0)?;
+    IX_UPDATED_AT.save(store, (&created_at_bytes, contract_id), &0)?;
+
+    // Contracts without an admin aren't in the admin index
+    if let Some(admin) = admin {
+        let admin_bytes = IndexValue::String(admin.into()).to_bytes();
+        ID_2_ADMIN.save(store, contract_id, &admin_bytes)?;
+        IX_ADMIN.save(store, (&admin_bytes, contract_id), &0)?;
+    }
 
-    Ok(resp.add_event(Event::new("factory-create").add_attributes(vec![
-        attr("contract_address", contract_address.to_string()),
-        attr("code_id", code_id.to_string()),
-        attr("admin", admin.to_owned()),
-    ])))
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_env, mock_info},
+        Uint64,
+    };
+
+    fn named_create_msg(name: &str) -> CreateMsg {
+        CreateMsg {
+            preset: None,
+            code_id: Some(Uint64::new(1)),
+            channel: None,
+            instantiate_msg: Default::default(),
+            name: Some(name.to_owned()),
+            label: name.to_owned(),
+            admin: None,
+            indices: None,
+            relations: None,
+            tags: None,
+            salt: None,
+            funds: None,
+            callback: None,
+        }
+    }
+
+    #[test]
+    fn rejects_duplicate_names_in_batch() {
+        let mut deps = mock_dependencies();
+        let ctx = Context {
+            deps: deps.as_mut(),
+            env: mock_env(),
+            info: mock_info("creator", &[]),
+        };
+        let msg = CreateBatchMsg {
+            contracts: vec![named_create_msg("a"), named_create_msg("b"), named_create_msg("a")],
+            policy: Some(BatchCreationPolicy::BestEffort),
+        };
+        let err = exec_create_batch(ctx, msg, None).unwrap_err();
+
+        assert!(matches!(err, ContractError::ValidationError { .. }));
+    }
 }
@@ -0,0 +1,249 @@
+use crate::{
+    error::ContractError,
+    state::{
+        models::{CreatorPolicy, CreatorPolicyScope, ScopedCreatorPolicy},
+        storage::{CONFIG_CREATOR_ALLOWLIST, CONFIG_CREATOR_DENYLIST, CONFIG_CREATOR_POLICIES, MANAGED_BY},
+    },
+};
+use cosmwasm_std::{attr, Addr, Response, Storage, Uint64};
+use cw_storage_plus::Map;
+
+use super::Context;
+
+pub fn exec_set_creator_policy(
+    ctx: Context,
+    msg: ScopedCreatorPolicy,
+) -> Result<Response, ContractError> {
+    let Context { deps, .. } = ctx;
+    let scope_key = msg.scope.to_key();
+
+    CONFIG_CREATOR_POLICIES.save(deps.storage, &scope_key, &msg)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "set_creator_policy"), attr("scope", scope_key)]))
+}
+
+pub fn exec_remove_creator_policy(
+    ctx: Context,
+    scope: CreatorPolicyScope,
+) -> Result<Response, ContractError> {
+    let Context { deps, .. } = ctx;
+    let scope_key = scope.to_key();
+
+    CONFIG_CREATOR_POLICIES.remove(deps.storage, &scope_key);
+
+    Ok(Response::new().add_attributes(vec![attr("action", "remove_creator_policy"), attr("scope", scope_key)]))
+}
+
+pub fn exec_update_creator_allowlist(
+    ctx: Context,
+    scope: CreatorPolicyScope,
+    add: Option<Vec<Addr>>,
+    remove: Option<Vec<Addr>>,
+) -> Result<Response, ContractError> {
+    update_creator_list(ctx, CONFIG_CREATOR_ALLOWLIST, scope, add, remove)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_creator_allowlist")]))
+}
+
+pub fn exec_update_creator_denylist(
+    ctx: Context,
+    scope: CreatorPolicyScope,
+    add: Option<Vec<Addr>>,
+    remove: Option<Vec<Addr>>,
+) -> Result<Response, ContractError> {
+    update_creator_list(ctx, CONFIG_CREATOR_DENYLIST, scope, add, remove)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_creator_denylist")]))
+}
+
+fn update_creator_list(
+    ctx: Context,
+    list: Map<(&str, &Addr), u8>,
+    scope: CreatorPolicyScope,
+    add: Option<Vec<Addr>>,
+    remove: Option<Vec<Addr>>,
+) -> Result<(), ContractError> {
+    let Context { deps, .. } = ctx;
+    let scope_key = scope.to_key();
+
+    for creator in add.unwrap_or_default().iter() {
+        let creator = deps.api.addr_validate(creator.as_str())?;
+        list.save(deps.storage, (&scope_key, &creator), &0)?;
+    }
+
+    for creator in remove.unwrap_or_default().iter() {
+        list.remove(deps.storage, (&scope_key, creator));
+    }
+
+    Ok(())
+}
+
+/// Ensure that the creator policy which applies to a new contract permits the
+/// sender to create it. The manager is always permitted.
+pub fn ensure_creator_allowed(
+    store: &dyn Storage,
+    creator: &Addr,
+    code_id: Uint64,
+    preset: Option<&String>,
+) -> Result<(), ContractError> {
+    if *creator == MANAGED_BY.load(store)? {
+        return Ok(());
+    }
+
+    // Use the most specific policy that exists
+    let mut scopes = Vec::with_capacity(3);
+    if let Some(preset) = preset {
+        scopes.push(CreatorPolicyScope::Preset(preset.to_owned()));
+    }
+    scopes.push(CreatorPolicyScope::CodeId(code_id));
+    scopes.push(CreatorPolicyScope::Global);
+
+    for scope in scopes.iter() {
+        let scope_key = scope.to_key();
+        if let Some(ScopedCreatorPolicy { policy, .. }) = CONFIG_CREATOR_POLICIES.may_load(store, &scope_key)? {
+            return match policy {
+                CreatorPolicy::Open => Ok(()),
+                CreatorPolicy::AllowlistOnly => {
+                    if CONFIG_CREATOR_ALLOWLIST.has(store, (&scope_key, creator)) {
+                        Ok(())
+                    } else {
+                        Err(ContractError::NotAuthorized {
+                            reason: format!("creator not in allowlist: {}", creator),
+                        })
+                    }
+                },
+                CreatorPolicy::Denylist => {
+                    if CONFIG_CREATOR_DENYLIST.has(store, (&scope_key, creator)) {
+                        Err(ContractError::NotAuthorized {
+                            reason: format!("creator is denylisted: {}", creator),
+                        })
+                    } else {
+                        Ok(())
+                    }
+                },
+            };
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{default_config, instantiate, MANAGER};
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_env, mock_info},
+        DepsMut,
+    };
+
+    const CODE_ID: Uint64 = Uint64::new(1);
+
+    fn ctx(deps: DepsMut) -> Context {
+        Context {
+            deps,
+            env: mock_env(),
+            info: mock_info(MANAGER, &[]),
+        }
+    }
+
+    fn set_policy(
+        deps: DepsMut,
+        scope: CreatorPolicyScope,
+        policy: CreatorPolicy,
+    ) {
+        exec_set_creator_policy(ctx(deps), ScopedCreatorPolicy { scope, policy }).unwrap();
+    }
+
+    fn is_allowed(
+        store: &dyn Storage,
+        creator: &str,
+        preset: Option<&str>,
+    ) -> bool {
+        let preset = preset.map(|p| p.to_owned());
+        ensure_creator_allowed(store, &Addr::unchecked(creator), CODE_ID, preset.as_ref()).is_ok()
+    }
+
+    #[test]
+    fn applies_allowlists_and_denylists() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), default_config());
+
+        // Without a policy, anyone may create
+        assert!(is_allowed(deps.as_ref().storage, "alice", None));
+
+        set_policy(deps.as_mut(), CreatorPolicyScope::Global, CreatorPolicy::AllowlistOnly);
+        assert!(!is_allowed(deps.as_ref().storage, "alice", None));
+        assert!(is_allowed(deps.as_ref().storage, MANAGER, None));
+
+        let alice = vec![Addr::unchecked("alice")];
+        exec_update_creator_allowlist(
+            ctx(deps.as_mut()),
+            CreatorPolicyScope::Global,
+            Some(alice.clone()),
+            None,
+        )
+        .unwrap();
+        assert!(CONFIG_CREATOR_ALLOWLIST.has(deps.as_ref().storage, ("", &alice[0])));
+        assert!(is_allowed(deps.as_ref().storage, "alice", None));
+        assert!(!is_allowed(deps.as_ref().storage, "bob", None));
+
+        exec_update_creator_allowlist(
+            ctx(deps.as_mut()),
+            CreatorPolicyScope::Global,
+            None,
+            Some(alice.clone()),
+        )
+        .unwrap();
+        assert!(!CONFIG_CREATOR_ALLOWLIST.has(deps.as_ref().storage, ("", &alice[0])));
+        assert!(!is_allowed(deps.as_ref().storage, "alice", None));
+
+        // A denylist policy ignores the allowlist
+        set_policy(deps.as_mut(), CreatorPolicyScope::Global, CreatorPolicy::Denylist);
+        exec_update_creator_denylist(ctx(deps.as_mut()), CreatorPolicyScope::Global, Some(alice), None).unwrap();
+        assert!(!is_allowed(deps.as_ref().storage, "alice", None));
+        assert!(is_allowed(deps.as_ref().storage, "bob", None));
+    }
+
+    #[test]
+    fn most_specific_policy_takes_precedence() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), default_config());
+
+        set_policy(deps.as_mut(), CreatorPolicyScope::Global, CreatorPolicy::AllowlistOnly);
+        set_policy(
+            deps.as_mut(),
+            CreatorPolicyScope::CodeId(CODE_ID),
+            CreatorPolicy::Denylist,
+        );
+        set_policy(
+            deps.as_mut(),
+            CreatorPolicyScope::Preset("vault".to_owned()),
+            CreatorPolicy::AllowlistOnly,
+        );
+        let bob = vec![Addr::unchecked("bob")];
+        exec_update_creator_denylist(
+            ctx(deps.as_mut()),
+            CreatorPolicyScope::CodeId(CODE_ID),
+            Some(bob.clone()),
+            None,
+        )
+        .unwrap();
+        exec_update_creator_allowlist(ctx(deps.as_mut()), CreatorPolicyScope::Global, Some(bob), None).unwrap();
+
+        // The code ID's denylist overrides the global allowlist
+        assert!(is_allowed(deps.as_ref().storage, "alice", None));
+        assert!(!is_allowed(deps.as_ref().storage, "bob", None));
+
+        // The preset's empty allowlist overrides both
+        assert!(!is_allowed(deps.as_ref().storage, "alice", Some("vault")));
+        assert!(!is_allowed(deps.as_ref().storage, "bob", Some("vault")));
+
+        // Presets without a policy of their own fall back to the code ID's
+        assert!(is_allowed(deps.as_ref().storage, "alice", Some("other")));
+
+        // Removing a policy falls back to the next most specific one
+        exec_remove_creator_policy(ctx(deps.as_mut()), CreatorPolicyScope::CodeId(CODE_ID)).unwrap();
+        assert!(!CONFIG_CREATOR_POLICIES.has(deps.as_ref().storage, &CreatorPolicyScope::CodeId(CODE_ID).to_key()));
+        assert!(!is_allowed(deps.as_ref().storage, "alice", None));
+        assert!(is_allowed(deps.as_ref().storage, "bob", None));
+    }
+}
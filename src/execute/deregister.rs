@@ -0,0 +1,267 @@
+use crate::{
+    error::ContractError,
+    math::sub_u32,
+    state::{
+        build_index_storage_key, build_reverse_mapping_storage_key,
+        storage::{
+            ContractId, IndexMap, CONTRACT_ADDR_2_ID, CONTRACT_COUNTER, CONTRACT_CUSTOM_IX_VALUES, CONTRACT_ID_2_ADDR,
+            CONTRACT_ID_2_IS_DEREGISTERING, CONTRACT_ID_2_IS_HIDDEN, CONTRACT_ID_2_LABEL, CONTRACT_ID_2_NAME,
+            CONTRACT_ID_2_PARITION, CONTRACT_ID_2_PREV_CODE_ID, CONTRACT_NAME_2_ID, CONTRACT_TAG_WEIGHTS, ID_2_ADMIN,
+            ID_2_CODE_ID, ID_2_CREATED_AT, ID_2_CREATED_BY, ID_2_UPDATED_AT, IX_ADMIN, IX_CODE_ID, IX_CREATED_AT,
+            IX_CREATED_BY, IX_REL_CONTRACT_ADDR, IX_UPDATED_AT, MIGRATIONS, MIGRATION_ERRORS, MIGRATION_PREV_CODE_IDS,
+            MIGRATION_REPLY_ID_2_STATE, MIGRATION_ROLLBACKS, MIGRATION_ROLLBACK_ERRORS,
+            MIGRATION_ROLLBACK_REPLY_ID_2_STATE,
+        },
+    },
+};
+use cosmwasm_std::{attr, Addr, Event, Order, Response, StdResult, Storage};
+use cw_storage_plus::Map;
+
+use super::{
+    update::{remove_relation, remove_tag},
+    Context,
+};
+
+const DEFAULT_LIMIT: u16 = 100;
+const MAX_LIMIT: u16 = 500;
+
+/// Remove every trace of a contract from the factory. Contracts with more
+/// tags and relations than the given limit are purged over several calls,
+/// during which the contract is hidden and can no longer be updated. The
+/// contract's remaining state is removed by the call that clears its last
+/// tags and relations.
+pub fn exec_deregister(
+    ctx: Context,
+    contract: Option<Addr>,
+    limit: Option<u16>,
+) -> Result<Response, ContractError> {
+    let Context { deps, info, .. } = ctx;
+
+    // Only moderators may deregister a contract other than the sender itself,
+    // which is checked before dispatching here.
+    let contract_addr = if let Some(contract_addr) = contract {
+        deps.api.addr_validate(contract_addr.as_str())?
+    } else {
+        info.sender.to_owned()
+    };
+
+    let contract_id = CONTRACT_ADDR_2_ID.load(deps.storage, &contract_addr)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize;
+
+    CONTRACT_ID_2_IS_DEREGISTERING.save(deps.storage, contract_id, &true)?;
+    CONTRACT_ID_2_IS_HIDDEN.save(deps.storage, contract_id, &true)?;
+
+    // Remove the next batch of tags
+    let tags = CONTRACT_TAG_WEIGHTS
+        .prefix(contract_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for tag_bytes in tags.iter() {
+        remove_tag(deps.storage, contract_id, tag_bytes)?;
+    }
+
+    // Remove the next batch of relations with whatever limit remains
+    let relations = IX_REL_CONTRACT_ADDR
+        .sub_prefix(contract_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .take(limit - tags.len())
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (edge, rel_addr) in relations.iter() {
+        remove_relation(deps.storage, contract_id, edge, rel_addr)?;
+    }
+
+    // Done once no tags or relations remain after removing this batch
+    let is_done = CONTRACT_TAG_WEIGHTS
+        .prefix(contract_id)
+        .keys_raw(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_none()
+        && IX_REL_CONTRACT_ADDR
+            .sub_prefix(contract_id)
+            .keys_raw(deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_none();
+    let mut resp = Response::new().add_attributes(vec![
+        attr("action", "deregister"),
+        attr("contract_address", contract_addr.to_string()),
+        attr("done", is_done.to_string()),
+    ]);
+
+    if is_done {
+        purge(deps.storage, contract_id, &contract_addr)?;
+        resp = resp.add_event(Event::new("factory-deregister").add_attributes(vec![
+            attr("contract_address", contract_addr.to_string()),
+            attr("contract_id", contract_id.to_string()),
+        ]));
+    }
+
+    Ok(resp)
+}
+
+/// Remove all of a contract's remaining state, once its tags and relations
+/// are gone.
+fn purge(
+    store: &mut dyn Storage,
+    contract_id: ContractId,
+    contract_addr: &Addr,
+) -> Result<(), ContractError> {
+    // Built-in indices and their reverse-lookup tables
+    for (index, reverse_map) in [
+        (IX_CODE_ID, ID_2_CODE_ID),
+        (IX_CREATED_AT, ID_2_CREATED_AT),
+        (IX_UPDATED_AT, ID_2_UPDATED_AT),
+        (IX_CREATED_BY, ID_2_CREATED_BY),
+        (IX_ADMIN, ID_2_ADMIN),
+    ] {
+        if let Some(bytes) = reverse_map.may_load(store, contract_id)? {
+            index.remove(store, (&bytes, contract_id));
+            reverse_map.remove(store, contract_id);
+        }
+    }
+
+    // Custom indices, whose current values are held by their reverse maps
+    let custom_index_names = CONTRACT_CUSTOM_IX_VALUES
+        .prefix(contract_id)
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for name in custom_index_names.iter() {
+        let storage_key = build_index_storage_key(name);
+        let map: IndexMap = Map::new(&storage_key);
+
+        let reverse_mapping_storage_key = build_reverse_mapping_storage_key(name);
+        let reverse_map: Map<ContractId, Vec<u8>> = Map::new(&reverse_mapping_storage_key);
+
+        if let Some(bytes) = reverse_map.may_load(store, contract_id)? {
+            map.remove(store, (&bytes, contract_id));
+            reverse_map.remove(store, contract_id);
+        }
+        CONTRACT_CUSTOM_IX_VALUES.remove(store, (contract_id, name));
+    }
+
+    // Errors and previous code IDs tracked for the contract by migration sessions
+    let session_names = MIGRATIONS
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for session_name in session_names.iter() {
+        if let Some(error) = MIGRATION_ERRORS.may_load(store, (session_name, contract_id))? {
+            MIGRATION_ERRORS.remove(store, (session_name, contract_id));
+            MIGRATION_REPLY_ID_2_STATE.remove(store, error.reply_id.u64());
+            MIGRATIONS.update(store, session_name, |migration| -> Result<_, ContractError> {
+                let mut migration = migration.unwrap();
+                migration.n_error = sub_u32(migration.n_error, 1)?;
+                Ok(migration)
+            })?;
+        }
+        MIGRATION_PREV_CODE_IDS.remove(store, (session_name, contract_id));
+        if let Some(error) = MIGRATION_ROLLBACK_ERRORS.may_load(store, (session_name, contract_id))? {
+            MIGRATION_ROLLBACK_ERRORS.remove(store, (session_name, contract_id));
+            MIGRATION_ROLLBACK_REPLY_ID_2_STATE.remove(store, error.reply_id.u64());
+            MIGRATION_ROLLBACKS.update(store, session_name, |rollback| -> Result<_, ContractError> {
+                let mut rollback = rollback.unwrap();
+                rollback.n_error = sub_u32(rollback.n_error, 1)?;
+                Ok(rollback)
+            })?;
+        }
+    }
+
+    // Built-in lookup tables
+    if let Some(name) = CONTRACT_ID_2_NAME.may_load(store, contract_id)? {
+        CONTRACT_NAME_2_ID.remove(store, &name);
+        CONTRACT_ID_2_NAME.remove(store, contract_id);
+    }
+
+    CONTRACT_ID_2_LABEL.remove(store, contract_id);
+    CONTRACT_ID_2_PARITION.remove(store, contract_id);
+    CONTRACT_ID_2_PREV_CODE_ID.remove(store, contract_id);
+    CONTRACT_ID_2_IS_HIDDEN.remove(store, contract_id);
+    CONTRACT_ID_2_IS_DEREGISTERING.remove(store, contract_id);
+    CONTRACT_ID_2_ADDR.remove(store, contract_id);
+    CONTRACT_ADDR_2_ID.remove(store, contract_addr);
+
+    CONTRACT_COUNTER.update(store, |n| -> Result<_, ContractError> { sub_u32(n, 1) })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{default_config, instantiate, register, relation, tag, MANAGER};
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_env, mock_info},
+        DepsMut,
+    };
+
+    fn deregister(
+        deps: DepsMut,
+        address: &str,
+        limit: u16,
+    ) -> Response {
+        let ctx = Context {
+            deps,
+            env: mock_env(),
+            info: mock_info(MANAGER, &[]),
+        };
+        exec_deregister(ctx, Some(Addr::unchecked(address)), Some(limit)).unwrap()
+    }
+
+    fn is_done(resp: &Response) -> bool {
+        resp.attributes.iter().any(|x| x.key == "done" && x.value == "true")
+    }
+
+    #[test]
+    fn purges_when_last_batch_fills_limit() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), default_config());
+        let contract_id = register(
+            deps.as_mut(),
+            "contract",
+            "creator",
+            vec![],
+            vec![tag("a"), tag("b")],
+            vec![relation("owner", "someone")],
+        );
+
+        let resp = deregister(deps.as_mut(), "contract", 3);
+
+        assert!(is_done(&resp));
+        assert!(!CONTRACT_ADDR_2_ID.has(&deps.storage, &Addr::unchecked("contract")));
+        assert!(!CONTRACT_ID_2_ADDR.has(&deps.storage, contract_id));
+        assert_eq!(CONTRACT_COUNTER.load(&deps.storage).unwrap(), 0);
+    }
+
+    #[test]
+    fn deregisters_over_several_calls() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), default_config());
+        let contract_id = register(
+            deps.as_mut(),
+            "contract",
+            "creator",
+            vec![],
+            vec![tag("a"), tag("b")],
+            vec![relation("owner", "someone")],
+        );
+
+        let resp = deregister(deps.as_mut(), "contract", 2);
+
+        assert!(!is_done(&resp));
+        assert!(CONTRACT_ID_2_IS_DEREGISTERING.has(&deps.storage, contract_id));
+        assert!(CONTRACT_TAG_WEIGHTS
+            .prefix(contract_id)
+            .keys_raw(&deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_none());
+
+        let resp = deregister(deps.as_mut(), "contract", 2);
+
+        assert!(is_done(&resp));
+        assert!(!CONTRACT_ADDR_2_ID.has(&deps.storage, &Addr::unchecked("contract")));
+        assert!(!CONTRACT_ID_2_IS_DEREGISTERING.has(&deps.storage, contract_id));
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{
+    error::ContractError,
+    math::add_u32,
+    msg::ImportMsg,
+    state::{
+        models::SubMsgContext,
+        storage::{CONTRACT_ADDR_2_ID, CONTRACT_ID_COUNTER, MANAGED_BY},
+    },
+};
+use cosmwasm_std::{attr, Event, Response};
+
+use super::{create::register_contract, update::apply_updates, Context};
+
+/// Index a contract that was instantiated outside of the factory, verifying
+/// its code ID, creator and admin on-chain. The contract is indexed as if it
+/// had been created at the time of import.
+pub fn exec_import(
+    ctx: Context,
+    msg: ImportMsg,
+) -> Result<Response, ContractError> {
+    let Context { deps, env, info } = ctx;
+    let ImportMsg {
+        address,
+        name,
+        label,
+        indices,
+        relations,
+        tags,
+    } = msg;
+
+    let contract_address = deps.api.addr_validate(address.as_str())?;
+
+    if CONTRACT_ADDR_2_ID.has(deps.storage, &contract_address) {
+        return Err(ContractError::ValidationError {
+            reason: format!("contract already registered: {}", contract_address),
+        });
+    }
+
+    let contract_info = deps.querier.query_wasm_contract_info(contract_address.to_owned())?;
+
+    // Generate contract ID
+    let contract_id = CONTRACT_ID_COUNTER.update(deps.storage, |n| -> Result<_, ContractError> { add_u32(n, 1) })? - 1;
+
+    let context = SubMsgContext {
+        code_id: contract_info.code_id.into(),
+        created_by: deps.api.addr_validate(&contract_info.creator)?,
+        admin: contract_info
+            .admin
+            .map(|admin| deps.api.addr_validate(&admin))
+            .transpose()?,
+        contract_id,
+        name,
+        label,
+        batch_id: None,
+        indices: indices.unwrap_or_default(),
+        relations: relations.unwrap_or_default(),
+        tags: tags.unwrap_or_default(),
+        callback: false,
+        fee: None,
+        funds: vec![],
+    };
+
+    register_contract(deps.storage, env.block.time, &contract_address, &context)?;
+
+    let is_manager = MANAGED_BY.load(deps.storage)? == info.sender;
+    apply_updates(
+        deps.storage,
+        contract_id,
+        is_manager,
+        &context.indices,
+        &context.tags,
+        &context.relations,
+    )?;
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "import")])
+        .add_event(Event::new("factory-import").add_attributes(vec![
+            attr("contract_address", contract_address.to_string()),
+            attr("contract_id", contract_id.to_string()),
+            attr("code_id", contract_info.code_id.to_string()),
+            attr("admin", context.admin.map(|a| a.to_string()).unwrap_or_default()),
+        ])))
+}
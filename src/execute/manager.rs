@@ -0,0 +1,97 @@
+use crate::{
+    error::ContractError,
+    state::{
+        models::PendingManager,
+        storage::{MANAGED_BY, PENDING_MANAGER},
+    },
+    util::ensure_is_manager,
+};
+use cosmwasm_std::{attr, Addr, Event, Response};
+use cw_utils::Expiration;
+
+use super::Context;
+
+/// Propose a new manager, who must accept before the transfer takes effect.
+/// Proposing replaces any previous pending proposal.
+pub fn exec_propose_manager(
+    ctx: Context,
+    address: Addr,
+    expiry: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let Context { deps, env, .. } = ctx;
+    let address = deps.api.addr_validate(address.as_str())?;
+
+    if let Some(expiry) = &expiry {
+        if expiry.is_expired(&env.block) {
+            return Err(ContractError::ValidationError {
+                reason: "manager proposal expiry is in the past".to_owned(),
+            });
+        }
+    }
+
+    PENDING_MANAGER.save(
+        deps.storage,
+        &PendingManager {
+            address: address.to_owned(),
+            expiry,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "propose_manager")])
+        .add_event(Event::new("factory-manager-propose").add_attributes(vec![attr("address", address.to_string())])))
+}
+
+/// Become the manager, as proposed by the current one.
+pub fn exec_accept_manager(ctx: Context) -> Result<Response, ContractError> {
+    let Context { deps, env, info } = ctx;
+
+    let pending = PENDING_MANAGER
+        .may_load(deps.storage)?
+        .ok_or_else(|| ContractError::ValidationError {
+            reason: "no manager proposed".to_owned(),
+        })?;
+
+    if info.sender != pending.address {
+        return Err(ContractError::NotAuthorized {
+            reason: "only the proposed manager can accept".to_owned(),
+        });
+    }
+
+    if pending.expiry.map(|x| x.is_expired(&env.block)).unwrap_or(false) {
+        return Err(ContractError::ValidationError {
+            reason: "manager proposal expired".to_owned(),
+        });
+    }
+
+    let prev_manager = MANAGED_BY.load(deps.storage)?;
+
+    MANAGED_BY.save(deps.storage, &pending.address)?;
+    PENDING_MANAGER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "accept_manager")])
+        .add_event(Event::new("factory-manager-transfer").add_attributes(vec![
+            attr("prev_manager", prev_manager.to_string()),
+            attr("manager", pending.address.to_string()),
+        ])))
+}
+
+/// Withdraw a pending proposal, either as the manager or the proposed manager.
+pub fn exec_cancel_manager(ctx: Context) -> Result<Response, ContractError> {
+    let Context { deps, info, .. } = ctx;
+
+    let pending = PENDING_MANAGER
+        .may_load(deps.storage)?
+        .ok_or_else(|| ContractError::ValidationError {
+            reason: "no manager proposed".to_owned(),
+        })?;
+
+    if info.sender != pending.address {
+        ensure_is_manager(deps.storage, &info.sender)?;
+    }
+
+    PENDING_MANAGER.remove(deps.storage);
+
+    Ok(Response::new().add_attributes(vec![attr("action", "cancel_manager")]))
+}
@@ -1,53 +1,93 @@
-use std::marker::PhantomData;
+use std::{collections::BTreeSet, marker::PhantomData};
 
 use crate::{
     error::ContractError,
     math::{add_u32, add_u64, sub_u32},
-    msg::{MigrationParams, SingletonMigrationParams},
+    msg::{
+        IndexRangeBound, IndexValue, IndexValueKind, MigrateMsgOverride, MigrationParams, MigrationTarget,
+        SingletonMigrationParams, TagWeightRangeBound,
+    },
     state::{
-        models::{Migration, MigrationError, MigrationErrorStrategy, MigrationStatus},
+        build_index_storage_key,
+        models::{
+            Migration, MigrationError, MigrationErrorStrategy, MigrationResult, MigrationRollback,
+            MigrationSessionRecord, MigrationStatus,
+        },
         storage::{
-            CONTRACT_ADDR_2_ID, CONTRACT_ID_2_ADDR, ID_2_CODE_ID, MIGRATIONS, MIGRATION_ERRORS,
-            MIGRATION_REPLY_ID_2_STATE, REPLY_ID_COUNTER,
+            ContractId, IndexMap, CONFIG_CUSTOM_INDICES, CONTRACT_ADDR_2_ID, CONTRACT_CUSTOM_IX_VALUES,
+            CONTRACT_ID_2_ADDR, CONTRACT_ID_2_NAME, CONTRACT_ID_2_PREV_CODE_ID, ID_2_CODE_ID, IX_CODE_ID,
+            IX_CREATED_BY, IX_MIGRATION_RESULTS_BY_CONTRACT, IX_MIGRATION_RESULTS_BY_SESSION, IX_REL_ADDR,
+            IX_WEIGHTED_TAG, MIGRATIONS, MIGRATION_ERRORS, MIGRATION_HISTORY, MIGRATION_NAME_2_SESSION_ID,
+            MIGRATION_PREV_CODE_IDS, MIGRATION_REPLY_ID_2_STATE, MIGRATION_RESULTS, MIGRATION_RESULT_ID_COUNTER,
+            MIGRATION_ROLLBACKS, MIGRATION_ROLLBACK_ERRORS, MIGRATION_ROLLBACK_REPLY_ID_2_STATE,
+            MIGRATION_SESSION_ID_COUNTER, REPLY_ID_COUNTER,
         },
     },
+    util::{render_template, resolve_migration_code_id},
 };
 use cosmwasm_std::{
-    attr, to_json_binary, DepsMut, Empty, Event, Order, Reply, Response, StdError, StdResult, SubMsg, SubMsgResult,
-    WasmMsg,
+    attr, from_json, to_json_binary, Addr, Binary, DepsMut, Empty, Env, Event, Order, Reply, Response, StdError,
+    StdResult, Storage, SubMsg, SubMsgResult, Uint64, WasmMsg,
 };
-use cw_storage_plus::Bound;
+use cw_storage_plus::{Bound, Map, PrimaryKey};
+use serde_json::Value;
 
 use super::Context;
 
 const DEFAULT_LIMIT: u16 = 50;
 const MAX_LIMIT: u16 = 100;
 
+type TargetContract = (ContractId, Addr);
+
 pub fn exec_migrate_one(
     ctx: Context,
     params: SingletonMigrationParams,
 ) -> Result<Response, ContractError> {
-    let Context { deps, .. } = ctx;
+    let Context { deps, env, .. } = ctx;
     let mut resp = Response::new().add_attributes(vec![attr("action", "migrate")]);
     let addr = deps.api.addr_validate(params.contract.as_str())?;
     let id = CONTRACT_ADDR_2_ID.load(deps.storage, &addr)?;
+    let to_code_id = resolve_migration_code_id(deps.storage, params.to_code_id, params.to_channel.as_ref())?;
 
     // Ensure we're migrating from the required code ID
     if let Some(from_code_id) = params.from_code_id {
         let code_id = ID_2_CODE_ID.load(deps.storage, id)?;
-        if u64::from_le_bytes(code_id.as_slice().try_into().unwrap()) != from_code_id.u64() {
+        if code_id != IndexValue::Uint64(from_code_id).to_bytes() {
             return Ok(resp);
         }
     }
 
-    resp = resp.add_submessage(SubMsg::new(WasmMsg::Migrate {
-        contract_addr: addr.to_string(),
-        new_code_id: params.to_code_id.u64(),
-        msg: params
-            .migrate_msg
-            .to_owned()
-            .unwrap_or_else(|| to_json_binary(&Empty {}).unwrap()),
-    }));
+    // The migration can't fail without reverting this tx, so the code ID index
+    // is updated right away.
+    let prev_code_id = set_code_id(deps.storage, id, to_code_id)?;
+
+    record_result(
+        deps.storage,
+        &MigrationResult {
+            session_id: None,
+            contract: addr.to_owned(),
+            prev_code_id: Some(prev_code_id),
+            code_id: to_code_id,
+            is_rollback: false,
+            error: None,
+            height: env.block.height,
+        },
+    )?;
+
+    resp = resp
+        .add_submessage(SubMsg::new(WasmMsg::Migrate {
+            contract_addr: addr.to_string(),
+            new_code_id: to_code_id.u64(),
+            msg: params
+                .migrate_msg
+                .to_owned()
+                .unwrap_or_else(|| to_json_binary(&Empty {}).unwrap()),
+        }))
+        .add_event(Event::new("migration-success").add_attributes(vec![
+            attr("migrated_contract_addr", addr.to_string()),
+            attr("prev_code_id", prev_code_id.to_string()),
+            attr("code_id", to_code_id.to_string()),
+        ]));
 
     Ok(resp)
 }
@@ -56,11 +96,33 @@ pub fn exec_begin_migration(
     ctx: Context,
     params: MigrationParams,
 ) -> Result<Response, ContractError> {
-    let Context { deps, .. } = ctx;
+    let Context { deps, env, info } = ctx;
     let mut params = params;
 
-    // TODO: Validate and normalize params
-    params.batch_size = Some(params.batch_size.unwrap_or(DEFAULT_LIMIT).clamp(0, MAX_LIMIT));
+    // Sessions that are no longer running may be replaced, starting afresh,
+    // unless contracts they migrated can still be rolled back. Cancelling a
+    // session discards what it needs for a rollback.
+    if let Some(migration) = MIGRATIONS.may_load(deps.storage, &params.name)? {
+        if migration.status == MigrationStatus::Running {
+            return Err(ContractError::MigrationExists { name: params.name });
+        }
+        if MIGRATION_PREV_CODE_IDS
+            .prefix(&params.name)
+            .keys_raw(deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_some()
+        {
+            return Err(ContractError::ValidationError {
+                reason: format!(
+                    "migration '{}' can still be rolled back; cancel it to reuse its name",
+                    params.name
+                ),
+            });
+        }
+        clear_session(deps.storage, &params.name)?;
+    }
+
+    normalize_params(deps.storage, &mut params)?;
 
     MIGRATIONS.save(
         deps.storage,
@@ -75,6 +137,26 @@ pub fn exec_begin_migration(
         },
     )?;
 
+    // Open the session's history record
+    let session_id = MIGRATION_SESSION_ID_COUNTER.may_load(deps.storage)?.unwrap_or_default();
+    MIGRATION_SESSION_ID_COUNTER.save(deps.storage, &add_u32(session_id, 1)?)?;
+    MIGRATION_NAME_2_SESSION_ID.save(deps.storage, &params.name, &session_id)?;
+    MIGRATION_HISTORY.save(
+        deps.storage,
+        session_id,
+        &MigrationSessionRecord {
+            id: session_id,
+            name: params.name.to_owned(),
+            started_by: info.sender,
+            params: params.to_owned(),
+            started_at_height: env.block.height,
+            ended_at_height: None,
+            n_success: 0,
+            n_error: 0,
+            status: MigrationStatus::Running,
+        },
+    )?;
+
     Ok(Response::new().add_attributes(vec![
         attr("action", "begin_migration"),
         attr("session_name", params.name.to_owned()),
@@ -85,45 +167,27 @@ pub fn exec_step_migration(
     ctx: Context,
     session_name: String,
 ) -> Result<Response, ContractError> {
-    let Context { deps, .. } = ctx;
+    let Context { deps, env, .. } = ctx;
 
     let mut migration = MIGRATIONS.load(deps.storage, &session_name)?;
 
-    if migration.status == MigrationStatus::Complete {
-        return Err(ContractError::MigrationComplete { name: session_name });
+    match migration.status {
+        MigrationStatus::Complete | MigrationStatus::Cancelled => {
+            return Err(ContractError::MigrationComplete { name: session_name })
+        },
+        MigrationStatus::Aborted => return Err(ContractError::MigrationRolledBack { name: session_name }),
+        MigrationStatus::Running => {},
     }
 
     let params = &migration.params;
     let batch_size = params.batch_size.unwrap();
 
-    // Exclusive range bound to resume iteration from
-    let min_bound = if let Some(cursor) = migration.cursor {
-        Some(Bound::Exclusive((cursor, PhantomData)))
-    } else {
-        None
-    };
-
-    // Load all contrct ID/Addrs to process in this batch
-    let entries: Vec<StdResult<_>> = CONTRACT_ID_2_ADDR
-        .range(deps.storage, min_bound, None, Order::Ascending)
-        .take(batch_size as usize)
-        .collect();
+    // Load the contract ID/Addrs to process in this batch
+    let (targets, next_cursor) = scan_migration_targets(deps.storage, params, migration.cursor.as_ref(), batch_size)?;
 
-    let mut migrate_submsgs: Vec<SubMsg> = Vec::with_capacity(entries.len());
-    let mut next_cursor_id: Option<u32> = None;
-
-    for result in entries {
-        let (id, addr) = result?;
-        next_cursor_id = Some(id);
-
-        // Ensure we're migrating from the required code ID
-        if let Some(from_code_id) = params.from_code_id {
-            let code_id = ID_2_CODE_ID.load(deps.storage, id)?;
-            if u64::from_le_bytes(code_id.as_slice().try_into().unwrap()) != from_code_id.u64() {
-                continue;
-            }
-        }
+    let mut migrate_submsgs: Vec<SubMsg> = Vec::with_capacity(targets.len());
 
+    for (id, addr) in targets {
         let reply_id = REPLY_ID_COUNTER
             .update(deps.storage, |n| -> Result<_, ContractError> { add_u64(n, 1u64) })?
             .u64()
@@ -133,25 +197,21 @@ pub fn exec_step_migration(
 
         migrate_submsgs.push(SubMsg::reply_always(
             WasmMsg::Migrate {
+                msg: build_migrate_msg(deps.storage, &MigrateMsgs::from(params), id, &addr)?,
                 contract_addr: addr.to_string(),
-                new_code_id: params.to_code_id.u64(),
-                msg: params
-                    .migrate_msg
-                    .to_owned()
-                    .unwrap_or_else(|| to_json_binary(&Empty {}).unwrap()),
+                new_code_id: params.to_code_id.unwrap().u64(),
             },
             reply_id,
         ))
     }
 
-    if migrate_submsgs.len() < batch_size as usize {
+    if next_cursor.is_none() {
         migration.status = MigrationStatus::Complete;
-        migration.cursor = None;
-    } else {
-        migration.cursor = next_cursor_id;
     }
+    migration.cursor = next_cursor;
 
     MIGRATIONS.save(deps.storage, &params.name, &migration)?;
+    record_session_progress(deps.storage, &params.name, &migration, env.block.height)?;
 
     Ok(Response::new().add_submessages(migrate_submsgs).add_attributes(vec![
         attr("action", "step_migration"),
@@ -164,9 +224,14 @@ pub fn exec_retry_migration(
     session_name: String,
     override_migration_params: Option<MigrationParams>,
 ) -> Result<Response, ContractError> {
-    let Context { deps, .. } = ctx;
+    let Context { deps, env, .. } = ctx;
 
     let mut migration = MIGRATIONS.load(deps.storage, &session_name)?;
+
+    if migration.status == MigrationStatus::Aborted {
+        return Err(ContractError::MigrationRolledBack { name: session_name });
+    }
+
     let mut migrate_submsgs: Vec<SubMsg> = Vec::with_capacity(migration.params.batch_size.unwrap() as usize);
     let mut next_cursor_id: Option<u32> = None;
 
@@ -175,20 +240,17 @@ pub fn exec_retry_migration(
     if let Some(mut override_params) = override_migration_params {
         // Don't let user set a custom name here, which could be out of sync
         // with the existing migration, which would mess things up.
-        // TODO: Validate and normalize params
         override_params.name = migration.params.name;
-        override_params.batch_size = Some(override_params.batch_size.unwrap_or(DEFAULT_LIMIT).clamp(0, MAX_LIMIT));
+        normalize_params(deps.storage, &mut override_params)?;
         migration.params = override_params;
     }
 
     let params = &migration.params;
 
     // Exclusive range bound to resume iteration from
-    let min_bound = if let Some(cursor) = migration.retry_cursor {
-        Some(Bound::Exclusive((cursor, PhantomData)))
-    } else {
-        None
-    };
+    let min_bound = migration
+        .retry_cursor
+        .map(|cursor| Bound::Exclusive((cursor, PhantomData)));
 
     let entries = MIGRATION_ERRORS
         .prefix(&session_name)
@@ -217,12 +279,9 @@ pub fn exec_retry_migration(
 
         migrate_submsgs.push(SubMsg::reply_always(
             WasmMsg::Migrate {
+                msg: build_migrate_msg(deps.storage, &MigrateMsgs::from(params), id, &error.contract)?,
                 contract_addr: error.contract.to_string(),
-                new_code_id: params.to_code_id.u64(),
-                msg: params
-                    .migrate_msg
-                    .to_owned()
-                    .unwrap_or_else(|| to_json_binary(&Empty {}).unwrap()),
+                new_code_id: params.to_code_id.unwrap().u64(),
             },
             reply_id,
         ))
@@ -230,12 +289,13 @@ pub fn exec_retry_migration(
 
     if migrate_submsgs.len() < params.batch_size.unwrap() as usize {
         migration.status = MigrationStatus::Complete;
-        migration.cursor = None;
+        migration.retry_cursor = None;
     } else {
-        migration.cursor = next_cursor_id;
+        migration.retry_cursor = next_cursor_id;
     }
 
     MIGRATIONS.save(deps.storage, &params.name, &migration)?;
+    record_session_progress(deps.storage, &params.name, &migration, env.block.height)?;
 
     Ok(Response::new().add_submessages(migrate_submsgs).add_attributes(vec![
         attr("action", "retry_migration"),
@@ -243,38 +303,407 @@ pub fn exec_retry_migration(
     ]))
 }
 
+/// Apply the default batch size, validate the target and resolve the code ID
+/// to migrate to, which is fixed for the rest of the session even if its
+/// channel changes.
+pub fn normalize_params(
+    store: &dyn Storage,
+    params: &mut MigrationParams,
+) -> Result<(), ContractError> {
+    params.batch_size = Some(params.batch_size.unwrap_or(DEFAULT_LIMIT).clamp(0, MAX_LIMIT));
+    params.to_code_id = Some(resolve_migration_code_id(
+        store,
+        params.to_code_id,
+        params.to_channel.as_ref(),
+    )?);
+    match &params.target {
+        Some(MigrationTarget::IndexRange { index, start, stop }) => {
+            let kind = CONFIG_CUSTOM_INDICES
+                .may_load(store, index)?
+                .ok_or_else(|| ContractError::ValidationError {
+                    reason: format!("custom index not registered: {}", index),
+                })?
+                .kind;
+            for bound in [start, stop].into_iter().flatten() {
+                let (IndexRangeBound::Exclusive(value) | IndexRangeBound::Inclusive(value)) = bound;
+                if value.kind() != kind {
+                    return Err(ContractError::ValidationError {
+                        reason: format!(
+                            "custom index '{}' expects {:?} bounds, got {:?}",
+                            index,
+                            kind,
+                            value.kind()
+                        ),
+                    });
+                }
+            }
+        },
+        Some(MigrationTarget::Addresses(addresses)) => {
+            for addr in addresses.iter() {
+                if !CONTRACT_ADDR_2_ID.has(store, addr) {
+                    return Err(ContractError::ValidationError {
+                        reason: format!("contract not found: {}", addr),
+                    });
+                }
+            }
+        },
+        _ => {},
+    }
+
+    validate_migrate_msgs(store, &MigrateMsgs::from(&*params))
+}
+
+/// The migrate message settings of a migration session or rollback.
+struct MigrateMsgs<'a> {
+    msg: Option<&'a Binary>,
+    overrides: &'a [MigrateMsgOverride],
+    template: bool,
+}
+
+impl<'a> From<&'a MigrationParams> for MigrateMsgs<'a> {
+    fn from(params: &'a MigrationParams) -> Self {
+        Self {
+            msg: params.migrate_msg.as_ref(),
+            overrides: params.migrate_msg_overrides.as_deref().unwrap_or_default(),
+            template: params.migrate_msg_template.unwrap_or_default(),
+        }
+    }
+}
+
+fn validate_migrate_msgs(
+    store: &dyn Storage,
+    msgs: &MigrateMsgs,
+) -> Result<(), ContractError> {
+    for x in msgs.overrides.iter() {
+        if !CONTRACT_ADDR_2_ID.has(store, &x.contract) {
+            return Err(ContractError::ValidationError {
+                reason: format!("contract not found: {}", x.contract),
+            });
+        }
+    }
+
+    // Catch malformed templates and unknown placeholders before any contract
+    // is migrated with them.
+    if msgs.template {
+        let templates = msgs
+            .msg
+            .into_iter()
+            .chain(msgs.overrides.iter().map(|x| &x.migrate_msg));
+        for template in templates {
+            let mut value: Value = from_json(template)?;
+            render_template(&mut value, &mut |key| {
+                resolve_placeholder(store, key, None)?;
+                Ok(Value::Null)
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the migrate message for a contract, preferring its override to the
+/// default migrate message and rendering it if it's a template.
+fn build_migrate_msg(
+    store: &dyn Storage,
+    msgs: &MigrateMsgs,
+    contract_id: ContractId,
+    contract_addr: &Addr,
+) -> Result<Binary, ContractError> {
+    let msg = msgs
+        .overrides
+        .iter()
+        .find(|x| x.contract == *contract_addr)
+        .map(|x| &x.migrate_msg)
+        .or(msgs.msg);
+
+    match msg {
+        Some(msg) if msgs.template => {
+            let mut value: Value = from_json(msg)?;
+            render_template(&mut value, &mut |key| {
+                resolve_placeholder(store, key, Some((contract_id, contract_addr)))
+            })?;
+            Ok(to_json_binary(&value)?)
+        },
+        Some(msg) => Ok(msg.to_owned()),
+        None => Ok(to_json_binary(&Empty {})?),
+    }
+}
+
+/// Get the value of a migrate message template placeholder for the given
+/// contract, or just validate the placeholder if no contract is given. Names
+/// and index values the contract doesn't have resolve to null.
+fn resolve_placeholder(
+    store: &dyn Storage,
+    key: &str,
+    contract: Option<(ContractId, &Addr)>,
+) -> Result<Value, ContractError> {
+    if let Some(index_name) = key.strip_prefix("index:") {
+        let index_name = index_name.to_owned();
+        let index =
+            CONFIG_CUSTOM_INDICES
+                .may_load(store, &index_name)?
+                .ok_or_else(|| ContractError::ValidationError {
+                    reason: format!("custom index not registered: {}", index_name),
+                })?;
+        return Ok(match contract {
+            Some((id, _)) => match CONTRACT_CUSTOM_IX_VALUES.may_load(store, (id, &index_name))? {
+                Some(bytes) => IndexValue::from_bytes(index.kind, &bytes)?.to_json_value(),
+                None => Value::Null,
+            },
+            None => Value::Null,
+        });
+    }
+    Ok(match (key, contract) {
+        ("contract_address" | "contract_id" | "name", None) => Value::Null,
+        ("contract_address", Some((_, addr))) => Value::String(addr.to_string()),
+        ("contract_id", Some((id, _))) => Value::from(id),
+        ("name", Some((id, _))) => CONTRACT_ID_2_NAME
+            .may_load(store, id)?
+            .map(Value::String)
+            .unwrap_or(Value::Null),
+        _ => {
+            return Err(ContractError::ValidationError {
+                reason: format!("unknown migrate msg placeholder: {}", key),
+            })
+        },
+    })
+}
+
+/// Scan the next batch of keys of the index selected by the session's target,
+/// skipping contracts not running `from_code_id` or already visited by the
+/// session. At most `batch_size` keys are scanned. The returned cursor is the
+/// last key scanned or None if the target has been exhausted.
+pub fn scan_migration_targets(
+    store: &dyn Storage,
+    params: &MigrationParams,
+    cursor: Option<&Binary>,
+    batch_size: u16,
+) -> Result<(Vec<TargetContract>, Option<Binary>), ContractError> {
+    let limit = batch_size as usize;
+
+    let mut keys: Vec<Vec<u8>> = match &params.target {
+        None => CONTRACT_ID_2_ADDR
+            .keys_raw(store, resume_from(cursor), None, Order::Ascending)
+            .take(limit)
+            .collect(),
+        Some(MigrationTarget::Tag {
+            tag,
+            min_weight,
+            max_weight,
+        }) => {
+            let tag_bytes = IndexValue::String(tag.to_owned()).to_bytes();
+            let min_bound = match (cursor, min_weight) {
+                (Some(_), _) => resume_from(cursor),
+                (None, Some(TagWeightRangeBound::Inclusive(w))) => {
+                    Some(Bound::Inclusive(((*w, ContractId::MIN), PhantomData)))
+                },
+                (None, Some(TagWeightRangeBound::Exclusive(w))) => {
+                    Some(Bound::Exclusive(((*w, ContractId::MAX), PhantomData)))
+                },
+                (None, None) => None,
+            };
+            let max_bound = match max_weight {
+                Some(TagWeightRangeBound::Inclusive(w)) => Some(Bound::Inclusive(((*w, ContractId::MAX), PhantomData))),
+                Some(TagWeightRangeBound::Exclusive(w)) => Some(Bound::Exclusive(((*w, ContractId::MIN), PhantomData))),
+                None => None,
+            };
+            IX_WEIGHTED_TAG
+                .sub_prefix(&tag_bytes)
+                .keys_raw(store, min_bound, max_bound, Order::Ascending)
+                .take(limit)
+                .collect()
+        },
+        Some(MigrationTarget::IndexRange { index, start, stop }) => {
+            let storage_key = build_index_storage_key(index);
+            let map: IndexMap = Map::new(storage_key.as_str());
+            let start_bytes = start.as_ref().map(|b| match b {
+                IndexRangeBound::Exclusive(v) => (false, v.to_bytes()),
+                IndexRangeBound::Inclusive(v) => (true, v.to_bytes()),
+            });
+            let stop_bytes = stop.as_ref().map(|b| match b {
+                IndexRangeBound::Exclusive(v) => (false, v.to_bytes()),
+                IndexRangeBound::Inclusive(v) => (true, v.to_bytes()),
+            });
+            let min_bound = match (cursor, &start_bytes) {
+                (Some(_), _) => resume_from(cursor),
+                (None, Some((true, bytes))) => {
+                    Some(Bound::Inclusive(((bytes.as_slice(), ContractId::MIN), PhantomData)))
+                },
+                (None, Some((false, bytes))) => {
+                    Some(Bound::Exclusive(((bytes.as_slice(), ContractId::MAX), PhantomData)))
+                },
+                (None, None) => None,
+            };
+            let max_bound = match &stop_bytes {
+                Some((true, bytes)) => Some(Bound::Inclusive(((bytes.as_slice(), ContractId::MAX), PhantomData))),
+                Some((false, bytes)) => Some(Bound::Exclusive(((bytes.as_slice(), ContractId::MIN), PhantomData))),
+                None => None,
+            };
+            map.keys_raw(store, min_bound, max_bound, Order::Ascending)
+                .take(limit)
+                .collect()
+        },
+        Some(MigrationTarget::RelatedTo { address, .. }) => IX_REL_ADDR
+            .sub_prefix(address.as_bytes())
+            .keys_raw(store, resume_from(cursor), None, Order::Ascending)
+            .take(limit)
+            .collect(),
+        Some(MigrationTarget::CreatedBy(creator)) => {
+            let creator_bytes = IndexValue::String(creator.to_string()).to_bytes();
+            IX_CREATED_BY
+                .prefix(&creator_bytes)
+                .keys_raw(store, resume_from(cursor), None, Order::Ascending)
+                .take(limit)
+                .collect()
+        },
+        Some(MigrationTarget::Addresses(addresses)) => {
+            // Visited in order of contract ID so that the cursor works the
+            // same way as it does when scanning every contract.
+            let mut ids = addresses
+                .iter()
+                .map(|addr| CONTRACT_ADDR_2_ID.load(store, addr))
+                .collect::<StdResult<Vec<ContractId>>>()?;
+            ids.sort_unstable();
+            ids.dedup();
+            let cursor_id = cursor.map(|c| contract_id_from_key(c.as_slice()));
+            ids.into_iter()
+                .filter(|id| cursor_id.map(|c| *id > c).unwrap_or(true))
+                .take(limit)
+                .map(|id| id.to_be_bytes().to_vec())
+                .collect()
+        },
+    };
+
+    let next_cursor = if limit > 0 && keys.len() == limit {
+        keys.last().map(|k| Binary::from(k.as_slice()))
+    } else {
+        None
+    };
+
+    // Relation edges are the fixed-width name followed by the optional value,
+    // prefixed by their length, so a relation name can only be matched by
+    // prefix once scanned. Keys are the length-prefixed edge followed by the ID.
+    if let Some(MigrationTarget::RelatedTo { name: Some(name), .. }) = &params.target {
+        let name_bytes = IndexValue::String(name.to_owned()).to_bytes();
+        keys.retain(|k| k[2..k.len() - 4].starts_with(&name_bytes));
+    }
+
+    let mut targets: Vec<TargetContract> = Vec::with_capacity(keys.len());
+    let mut visited: BTreeSet<ContractId> = BTreeSet::new();
+
+    for key in keys.iter() {
+        let id = contract_id_from_key(key);
+
+        // A contract may come up more than once, like when it relates to the
+        // address more than once or its index value changes between batches,
+        // so skip contracts visited earlier in this batch or already migrated,
+        // successfully or not, by an earlier one.
+        if !visited.insert(id)
+            || MIGRATION_PREV_CODE_IDS.has(store, (&params.name, id))
+            || MIGRATION_ERRORS.has(store, (&params.name, id))
+        {
+            continue;
+        }
+
+        // Ensure we're migrating from the required code ID
+        if let Some(from_code_id) = params.from_code_id {
+            let code_id = ID_2_CODE_ID.load(store, id)?;
+            if code_id != IndexValue::Uint64(from_code_id).to_bytes() {
+                continue;
+            }
+        }
+
+        targets.push((id, CONTRACT_ID_2_ADDR.load(store, id)?));
+    }
+
+    Ok((targets, next_cursor))
+}
+
+/// Exclusive bound from which to resume scanning a target's index.
+fn resume_from<'a, K: PrimaryKey<'a>>(cursor: Option<&Binary>) -> Option<Bound<'a, K>> {
+    cursor.map(|c| Bound::ExclusiveRaw(c.to_vec()))
+}
+
+/// Every index a migration can target ends its keys with the contract ID.
+fn contract_id_from_key(key: &[u8]) -> ContractId {
+    let mut id_bytes = [0u8; 4];
+    id_bytes.copy_from_slice(&key[key.len() - 4..]);
+    ContractId::from_be_bytes(id_bytes)
+}
+
 pub fn exec_cancel_migration(
     ctx: Context,
     session_name: String,
 ) -> Result<Response, ContractError> {
-    let Context { deps, .. } = ctx;
+    let Context { deps, env, .. } = ctx;
+
+    // Close the session's history record, which outlives the session
+    if let Some(mut migration) = MIGRATIONS.may_load(deps.storage, &session_name)? {
+        migration.status = MigrationStatus::Cancelled;
+        record_session_progress(deps.storage, &session_name, &migration, env.block.height)?;
+    }
 
-    MIGRATIONS.remove(deps.storage, &session_name);
+    clear_session(deps.storage, &session_name)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "cancel_migration"),
+        attr("session_name", session_name.to_owned()),
+    ]))
+}
+
+/// Remove everything tracked for a session under its name, leaving only its
+/// history record.
+fn clear_session(
+    store: &mut dyn Storage,
+    session_name: &String,
+) -> Result<(), ContractError> {
+    MIGRATIONS.remove(store, session_name);
+    MIGRATION_NAME_2_SESSION_ID.remove(store, session_name);
 
     for result in MIGRATION_ERRORS
-        .prefix(&session_name)
-        .range(deps.storage, None, None, Order::Ascending)
+        .prefix(session_name)
+        .range(store, None, None, Order::Ascending)
         .collect::<Vec<StdResult<_>>>()
     {
         let (id, error) = result?;
-        MIGRATION_REPLY_ID_2_STATE.remove(deps.storage, error.reply_id.u64());
-        MIGRATION_ERRORS.remove(deps.storage, (&session_name, id));
+        MIGRATION_REPLY_ID_2_STATE.remove(store, error.reply_id.u64());
+        MIGRATION_ERRORS.remove(store, (session_name, id));
     }
 
-    Ok(Response::new().add_attributes(vec![
-        attr("action", "cancel_migration"),
-        attr("session_name", session_name.to_owned()),
-    ]))
+    MIGRATION_ROLLBACKS.remove(store, session_name);
+
+    for result in MIGRATION_ROLLBACK_ERRORS
+        .prefix(session_name)
+        .range(store, None, None, Order::Ascending)
+        .collect::<Vec<StdResult<_>>>()
+    {
+        let (id, error) = result?;
+        MIGRATION_ROLLBACK_REPLY_ID_2_STATE.remove(store, error.reply_id.u64());
+        MIGRATION_ROLLBACK_ERRORS.remove(store, (session_name, id));
+    }
+
+    for id in MIGRATION_PREV_CODE_IDS
+        .prefix(session_name)
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?
+    {
+        MIGRATION_PREV_CODE_IDS.remove(store, (session_name, id));
+    }
+
+    Ok(())
 }
 
 pub fn handle_migration_reply(
     deps: DepsMut,
+    env: Env,
     reply: Reply,
 ) -> Result<Response, ContractError> {
     let (session_name, contract_id) = MIGRATION_REPLY_ID_2_STATE.load(deps.storage, reply.id)?;
     let contract_addr = CONTRACT_ID_2_ADDR.load(deps.storage, contract_id)?;
 
     let mut migration = MIGRATIONS.load(deps.storage, &session_name)?;
+    let session_id = MIGRATION_NAME_2_SESSION_ID.may_load(deps.storage, &session_name)?;
+    let to_code_id = migration.params.to_code_id.unwrap();
     let mut resp = Response::new();
 
     match reply.result {
@@ -288,9 +717,32 @@ pub fn handle_migration_reply(
                 migration.n_error = sub_u32(migration.n_error, 1)?;
             }
 
+            let prev_code_id = set_code_id(deps.storage, contract_id, to_code_id)?;
+
+            // Remember the code ID to roll back to, unless the contract was
+            // already migrated earlier in the session.
+            if !MIGRATION_PREV_CODE_IDS.has(deps.storage, (&session_name, contract_id)) {
+                MIGRATION_PREV_CODE_IDS.save(deps.storage, (&session_name, contract_id), &prev_code_id)?;
+            }
+
+            record_result(
+                deps.storage,
+                &MigrationResult {
+                    session_id,
+                    contract: contract_addr.to_owned(),
+                    prev_code_id: Some(prev_code_id),
+                    code_id: to_code_id,
+                    is_rollback: false,
+                    error: None,
+                    height: env.block.height,
+                },
+            )?;
+
             resp = resp.add_event(Event::new("migration-success").add_attributes(vec![
                 attr("migrated_contract_addr", contract_addr.to_string()),
                 attr("session_name", session_name.to_owned()),
+                attr("prev_code_id", prev_code_id.to_string()),
+                attr("code_id", to_code_id.to_string()),
             ]));
         },
         SubMsgResult::Err(e) => {
@@ -303,6 +755,19 @@ pub fn handle_migration_reply(
             // to retry later via retry_migration
             migration.n_error = add_u32(migration.n_error, 1)?;
 
+            record_result(
+                deps.storage,
+                &MigrationResult {
+                    session_id,
+                    contract: contract_addr.to_owned(),
+                    prev_code_id: None,
+                    code_id: to_code_id,
+                    is_rollback: false,
+                    error: Some(e.to_string()),
+                    height: env.block.height,
+                },
+            )?;
+
             MIGRATION_ERRORS.save(
                 deps.storage,
                 (&session_name, contract_id),
@@ -321,6 +786,645 @@ pub fn handle_migration_reply(
     };
 
     MIGRATIONS.save(deps.storage, &session_name, &migration)?;
+    record_session_progress(deps.storage, &session_name, &migration, env.block.height)?;
+
+    Ok(resp)
+}
+
+pub fn exec_rollback_migration(
+    ctx: Context,
+    session_name: String,
+    migrate_msg: Option<Binary>,
+    migrate_msg_overrides: Option<Vec<MigrateMsgOverride>>,
+    migrate_msg_template: Option<bool>,
+) -> Result<Response, ContractError> {
+    let Context { deps, env, .. } = ctx;
+
+    let msgs = MigrateMsgs {
+        msg: migrate_msg.as_ref(),
+        overrides: migrate_msg_overrides.as_deref().unwrap_or_default(),
+        template: migrate_msg_template.unwrap_or_default(),
+    };
+    validate_migrate_msgs(deps.storage, &msgs)?;
+
+    let mut migration = MIGRATIONS.load(deps.storage, &session_name)?;
+    let batch_size = migration.params.batch_size.unwrap();
+
+    // Stop the session from migrating any more contracts
+    migration.status = MigrationStatus::Aborted;
+    MIGRATIONS.save(deps.storage, &session_name, &migration)?;
+    record_session_progress(deps.storage, &session_name, &migration, env.block.height)?;
+
+    // Start a new pass if this is the first or the last one completed
+    let mut rollback = match MIGRATION_ROLLBACKS.may_load(deps.storage, &session_name)? {
+        Some(rollback) if rollback.status == MigrationStatus::Running => rollback,
+        prev => MigrationRollback {
+            status: MigrationStatus::Running,
+            cursor: None,
+            n_success: prev.as_ref().map(|x| x.n_success).unwrap_or_default(),
+            n_error: prev.as_ref().map(|x| x.n_error).unwrap_or_default(),
+        },
+    };
+
+    // Exclusive range bound to resume iteration from
+    let min_bound = rollback.cursor.map(|cursor| Bound::Exclusive((cursor, PhantomData)));
+
+    let entries = MIGRATION_PREV_CODE_IDS
+        .prefix(&session_name)
+        .range(deps.storage, min_bound, None, Order::Ascending)
+        .take(batch_size as usize)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut rollback_submsgs: Vec<SubMsg> = Vec::with_capacity(entries.len());
+
+    for (id, prev_code_id) in entries.iter() {
+        let reply_id = REPLY_ID_COUNTER
+            .update(deps.storage, |n| -> Result<_, ContractError> { add_u64(n, 1u64) })?
+            .u64()
+            - 1;
+
+        MIGRATION_ROLLBACK_REPLY_ID_2_STATE.save(deps.storage, reply_id, &(session_name.to_owned(), *id))?;
+
+        // Remove any error from a previous pass, which will be re-added in the
+        // reply handler if it errors out again.
+        if MIGRATION_ROLLBACK_ERRORS.has(deps.storage, (&session_name, *id)) {
+            MIGRATION_ROLLBACK_ERRORS.remove(deps.storage, (&session_name, *id));
+            rollback.n_error = sub_u32(rollback.n_error, 1)?;
+        }
+
+        let contract_addr = CONTRACT_ID_2_ADDR.load(deps.storage, *id)?;
+
+        rollback_submsgs.push(SubMsg::reply_always(
+            WasmMsg::Migrate {
+                msg: build_migrate_msg(deps.storage, &msgs, *id, &contract_addr)?,
+                contract_addr: contract_addr.to_string(),
+                new_code_id: prev_code_id.u64(),
+            },
+            reply_id,
+        ))
+    }
+
+    if entries.len() < batch_size as usize {
+        rollback.status = MigrationStatus::Complete;
+        rollback.cursor = None;
+    } else {
+        rollback.cursor = entries.last().map(|(id, _)| *id);
+    }
+
+    MIGRATION_ROLLBACKS.save(deps.storage, &session_name, &rollback)?;
+
+    Ok(Response::new().add_submessages(rollback_submsgs).add_attributes(vec![
+        attr("action", "rollback_migration"),
+        attr("session_name", session_name.to_owned()),
+    ]))
+}
+
+pub fn handle_rollback_reply(
+    deps: DepsMut,
+    env: Env,
+    reply: Reply,
+) -> Result<Response, ContractError> {
+    let (session_name, contract_id) = MIGRATION_ROLLBACK_REPLY_ID_2_STATE.load(deps.storage, reply.id)?;
+    let contract_addr = CONTRACT_ID_2_ADDR.load(deps.storage, contract_id)?;
+
+    MIGRATION_ROLLBACK_REPLY_ID_2_STATE.remove(deps.storage, reply.id);
+
+    let mut rollback = MIGRATION_ROLLBACKS.load(deps.storage, &session_name)?;
+    let session_id = MIGRATION_NAME_2_SESSION_ID.may_load(deps.storage, &session_name)?;
+    let to_code_id = MIGRATION_PREV_CODE_IDS.load(deps.storage, (&session_name, contract_id))?;
+    let mut resp = Response::new();
+
+    match reply.result {
+        SubMsgResult::Ok(_) => {
+            rollback.n_success = add_u32(rollback.n_success, 1)?;
+
+            let prev_code_id = set_code_id(deps.storage, contract_id, to_code_id)?;
+
+            record_result(
+                deps.storage,
+                &MigrationResult {
+                    session_id,
+                    contract: contract_addr.to_owned(),
+                    prev_code_id: Some(prev_code_id),
+                    code_id: to_code_id,
+                    is_rollback: true,
+                    error: None,
+                    height: env.block.height,
+                },
+            )?;
+
+            // The contract is done, so later passes skip over it
+            MIGRATION_PREV_CODE_IDS.remove(deps.storage, (&session_name, contract_id));
+
+            resp = resp.add_event(Event::new("migration-rollback-success").add_attributes(vec![
+                attr("migrated_contract_addr", contract_addr.to_string()),
+                attr("session_name", session_name.to_owned()),
+                attr("prev_code_id", prev_code_id.to_string()),
+                attr("code_id", to_code_id.to_string()),
+            ]));
+        },
+        SubMsgResult::Err(e) => {
+            rollback.n_error = add_u32(rollback.n_error, 1)?;
+
+            record_result(
+                deps.storage,
+                &MigrationResult {
+                    session_id,
+                    contract: contract_addr.to_owned(),
+                    prev_code_id: None,
+                    code_id: to_code_id,
+                    is_rollback: true,
+                    error: Some(e.to_string()),
+                    height: env.block.height,
+                },
+            )?;
+
+            MIGRATION_ROLLBACK_ERRORS.save(
+                deps.storage,
+                (&session_name, contract_id),
+                &MigrationError {
+                    reply_id: reply.id.into(),
+                    contract: contract_addr.to_owned(),
+                    error: e.to_string(),
+                },
+            )?;
+
+            resp = resp.add_event(Event::new("migration-rollback-error").add_attributes(vec![
+                attr("migrated_contract_addr", contract_addr.to_string()),
+                attr("session_name", session_name.to_owned()),
+            ]));
+        },
+    };
+
+    MIGRATION_ROLLBACKS.save(deps.storage, &session_name, &rollback)?;
 
     Ok(resp)
 }
+
+/// Move a migrated contract to its new code ID in the code ID index and
+/// reverse map, recording and returning its previous code ID.
+pub fn set_code_id(
+    store: &mut dyn Storage,
+    contract_id: ContractId,
+    code_id: Uint64,
+) -> Result<Uint64, ContractError> {
+    let prev_code_id_bytes = ID_2_CODE_ID.load(store, contract_id)?;
+    let prev_code_id = match IndexValue::from_bytes(IndexValueKind::Uint64, &prev_code_id_bytes)? {
+        IndexValue::Uint64(n) => n,
+        _ => return Err(ContractError::Std(StdError::generic_err("expected Uint64 code ID"))),
+    };
+    let code_id_bytes = IndexValue::Uint64(code_id).to_bytes();
+
+    IX_CODE_ID.remove(store, (&prev_code_id_bytes, contract_id));
+    IX_CODE_ID.save(store, (&code_id_bytes, contract_id), &0)?;
+    ID_2_CODE_ID.save(store, contract_id, &code_id_bytes)?;
+    CONTRACT_ID_2_PREV_CODE_ID.save(store, contract_id, &prev_code_id)?;
+
+    Ok(prev_code_id)
+}
+
+/// Mirror a session's progress onto its history record, if it has one. Sessions
+/// begun before the history was kept don't.
+fn record_session_progress(
+    store: &mut dyn Storage,
+    session_name: &String,
+    migration: &Migration,
+    height: u64,
+) -> Result<(), ContractError> {
+    if let Some(session_id) = MIGRATION_NAME_2_SESSION_ID.may_load(store, session_name)? {
+        MIGRATION_HISTORY.update(store, session_id, |record| -> Result<_, ContractError> {
+            let mut record = record.unwrap();
+            record.params = migration.params.to_owned();
+            record.n_success = migration.n_success;
+            record.n_error = migration.n_error;
+            record.status = migration.status.to_owned();
+            record.ended_at_height = if migration.status == MigrationStatus::Running {
+                None
+            } else {
+                Some(height)
+            };
+            Ok(record)
+        })?;
+    }
+    Ok(())
+}
+
+/// Append the outcome of migrating a contract to the history.
+fn record_result(
+    store: &mut dyn Storage,
+    result: &MigrationResult,
+) -> Result<(), ContractError> {
+    let result_id = MIGRATION_RESULT_ID_COUNTER.may_load(store)?.unwrap_or_default();
+    MIGRATION_RESULT_ID_COUNTER.save(store, &add_u64(result_id, 1u64)?.u64())?;
+    MIGRATION_RESULTS.save(store, result_id, result)?;
+    IX_MIGRATION_RESULTS_BY_CONTRACT.save(store, (&result.contract, result_id), &0)?;
+    if let Some(session_id) = result.session_id {
+        IX_MIGRATION_RESULTS_BY_SESSION.save(store, (session_id, result_id), &0)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        execute::set_index::exec_set_index,
+        msg::{IndexUpdate, SetIndexMsg},
+        testing::{default_config, instantiate, register, relation, MANAGER},
+    };
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_env, mock_info},
+        CosmosMsg, SubMsgResponse,
+    };
+
+    const SESSION: &str = "upgrade";
+
+    fn migration_params() -> MigrationParams {
+        MigrationParams {
+            name: SESSION.to_owned(),
+            batch_size: None,
+            error_strategy: MigrationErrorStrategy::Retry,
+            migrate_msg: None,
+            migrate_msg_overrides: None,
+            migrate_msg_template: None,
+            from_code_id: None,
+            to_code_id: Some(Uint64::new(2)),
+            to_channel: None,
+            target: None,
+        }
+    }
+
+    fn related_to_params(name: Option<&str>) -> MigrationParams {
+        MigrationParams {
+            target: Some(MigrationTarget::RelatedTo {
+                address: Addr::unchecked("dao"),
+                name: name.map(|n| n.to_owned()),
+            }),
+            ..migration_params()
+        }
+    }
+
+    fn ctx(deps: DepsMut) -> Context {
+        Context {
+            deps,
+            env: mock_env(),
+            info: mock_info(MANAGER, &[]),
+        }
+    }
+
+    /// Reply to a migrate submessage with success or failure, returning the
+    /// contract address and code ID it migrated to.
+    fn reply_to(
+        submsg: &SubMsg,
+        ok: bool,
+    ) -> (Reply, String, u64) {
+        let CosmosMsg::Wasm(WasmMsg::Migrate {
+            contract_addr,
+            new_code_id,
+            ..
+        }) = &submsg.msg
+        else {
+            panic!("expected a migrate msg");
+        };
+        let result = if ok {
+            SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            })
+        } else {
+            SubMsgResult::Err("migration failed".to_owned())
+        };
+        (Reply { id: submsg.id, result }, contract_addr.to_owned(), *new_code_id)
+    }
+
+    fn code_id_of(
+        store: &dyn Storage,
+        id: ContractId,
+    ) -> Uint64 {
+        let bytes = ID_2_CODE_ID.load(store, id).unwrap();
+        match IndexValue::from_bytes(IndexValueKind::Uint64, &bytes).unwrap() {
+            IndexValue::Uint64(n) => n,
+            _ => panic!("expected Uint64 code ID"),
+        }
+    }
+
+    fn ids(targets: &[TargetContract]) -> Vec<ContractId> {
+        targets.iter().map(|(id, _)| *id).collect()
+    }
+
+    #[test]
+    fn caps_keys_scanned_per_batch() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), default_config());
+        let a = register(
+            deps.as_mut(),
+            "a",
+            "creator",
+            vec![],
+            vec![],
+            vec![relation("x", "dao"), relation("y", "dao")],
+        );
+        register(
+            deps.as_mut(),
+            "b",
+            "creator",
+            vec![],
+            vec![],
+            vec![relation("y", "dao")],
+        );
+        let c = register(
+            deps.as_mut(),
+            "c",
+            "creator",
+            vec![],
+            vec![],
+            vec![relation("x", "dao")],
+        );
+        let params = related_to_params(Some("x"));
+
+        // Relations named "x" come first, filling the first batch
+        let (targets, cursor) = scan_migration_targets(deps.as_ref().storage, &params, None, 2).unwrap();
+        assert_eq!(ids(&targets), vec![a, c]);
+        assert!(cursor.is_some());
+
+        // The next batch scans only relations named "y", so it's empty but
+        // still advances the cursor
+        let (targets, cursor) = scan_migration_targets(deps.as_ref().storage, &params, cursor.as_ref(), 2).unwrap();
+        assert!(targets.is_empty());
+        assert!(cursor.is_some());
+
+        let (targets, cursor) = scan_migration_targets(deps.as_ref().storage, &params, cursor.as_ref(), 2).unwrap();
+        assert!(targets.is_empty());
+        assert_eq!(cursor, None);
+    }
+
+    #[test]
+    fn skips_contracts_already_visited() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), default_config());
+        let a = register(
+            deps.as_mut(),
+            "a",
+            "creator",
+            vec![],
+            vec![],
+            vec![relation("x", "dao"), relation("y", "dao")],
+        );
+        let b = register(
+            deps.as_mut(),
+            "b",
+            "creator",
+            vec![],
+            vec![],
+            vec![relation("y", "dao")],
+        );
+        let c = register(
+            deps.as_mut(),
+            "c",
+            "creator",
+            vec![],
+            vec![],
+            vec![relation("x", "dao")],
+        );
+        let params = related_to_params(None);
+
+        // Contract "a" relates to the address twice but is only targeted once
+        let (targets, cursor) = scan_migration_targets(deps.as_ref().storage, &params, None, 10).unwrap();
+        assert_eq!(ids(&targets), vec![a, c, b]);
+        assert_eq!(cursor, None);
+
+        // Contracts migrated or failed by an earlier batch are skipped
+        MIGRATION_PREV_CODE_IDS
+            .save(deps.as_mut().storage, (&params.name, a), &Uint64::new(1))
+            .unwrap();
+        MIGRATION_ERRORS
+            .save(
+                deps.as_mut().storage,
+                (&params.name, b),
+                &MigrationError {
+                    contract: Addr::unchecked("b"),
+                    error: "failed".to_owned(),
+                    reply_id: Uint64::new(1),
+                },
+            )
+            .unwrap();
+        let (targets, cursor) = scan_migration_targets(deps.as_ref().storage, &params, None, 10).unwrap();
+        assert_eq!(ids(&targets), vec![c]);
+        assert_eq!(cursor, None);
+    }
+
+    #[test]
+    fn rolls_back_migrated_contracts() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), default_config());
+        let a = register(deps.as_mut(), "a", "creator", vec![], vec![], vec![]);
+        let b = register(deps.as_mut(), "b", "creator", vec![], vec![], vec![]);
+
+        exec_begin_migration(ctx(deps.as_mut()), migration_params()).unwrap();
+        let resp = exec_step_migration(ctx(deps.as_mut()), SESSION.to_owned()).unwrap();
+        assert_eq!(resp.messages.len(), 2);
+        for submsg in resp.messages.iter() {
+            let (reply, _, code_id) = reply_to(submsg, true);
+            assert_eq!(code_id, 2);
+            handle_migration_reply(deps.as_mut(), mock_env(), reply).unwrap();
+        }
+        assert_eq!(code_id_of(deps.as_ref().storage, a), Uint64::new(2));
+        assert_eq!(code_id_of(deps.as_ref().storage, b), Uint64::new(2));
+        let session_name = SESSION.to_owned();
+        assert_eq!(
+            MIGRATION_PREV_CODE_IDS
+                .load(deps.as_ref().storage, (&session_name, a))
+                .unwrap(),
+            Uint64::new(1)
+        );
+        assert_eq!(
+            MIGRATIONS.load(deps.as_ref().storage, &session_name).unwrap().status,
+            MigrationStatus::Complete
+        );
+
+        // The first pass rolls back one contract and fails on the other
+        let resp = exec_rollback_migration(ctx(deps.as_mut()), SESSION.to_owned(), None, None, None).unwrap();
+        assert_eq!(resp.messages.len(), 2);
+        assert_eq!(
+            MIGRATIONS.load(deps.as_ref().storage, &session_name).unwrap().status,
+            MigrationStatus::Aborted
+        );
+        for submsg in resp.messages.iter() {
+            let (reply, addr, code_id) = reply_to(submsg, submsg.id == resp.messages[0].id);
+            assert_eq!(code_id, 1);
+            assert!(addr == "a" || addr == "b");
+            handle_rollback_reply(deps.as_mut(), mock_env(), reply).unwrap();
+        }
+        assert_eq!(code_id_of(deps.as_ref().storage, a), Uint64::new(1));
+        assert_eq!(code_id_of(deps.as_ref().storage, b), Uint64::new(2));
+        assert!(!MIGRATION_PREV_CODE_IDS.has(deps.as_ref().storage, (&session_name, a)));
+        assert!(MIGRATION_PREV_CODE_IDS.has(deps.as_ref().storage, (&session_name, b)));
+        assert!(MIGRATION_ROLLBACK_ERRORS.has(deps.as_ref().storage, (&session_name, b)));
+        let rollback = MIGRATION_ROLLBACKS.load(deps.as_ref().storage, &session_name).unwrap();
+        assert_eq!(
+            (rollback.status, rollback.n_success, rollback.n_error),
+            (MigrationStatus::Complete, 1, 1)
+        );
+
+        // The rolled back session can't be resumed
+        let err = exec_step_migration(ctx(deps.as_mut()), SESSION.to_owned()).unwrap_err();
+        assert!(matches!(err, ContractError::MigrationRolledBack { .. }));
+
+        // The next pass retries only the contract left over
+        let resp = exec_rollback_migration(ctx(deps.as_mut()), SESSION.to_owned(), None, None, None).unwrap();
+        assert_eq!(resp.messages.len(), 1);
+        assert!(!MIGRATION_ROLLBACK_ERRORS.has(deps.as_ref().storage, (&session_name, b)));
+        let (reply, addr, _) = reply_to(&resp.messages[0], true);
+        assert_eq!(addr, "b");
+        handle_rollback_reply(deps.as_mut(), mock_env(), reply).unwrap();
+        assert_eq!(code_id_of(deps.as_ref().storage, b), Uint64::new(1));
+        assert!(!MIGRATION_PREV_CODE_IDS.has(deps.as_ref().storage, (&session_name, b)));
+        let rollback = MIGRATION_ROLLBACKS.load(deps.as_ref().storage, &session_name).unwrap();
+        assert_eq!((rollback.n_success, rollback.n_error), (2, 0));
+    }
+
+    #[test]
+    fn renders_migrate_msg_templates() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), default_config());
+        let index = SetIndexMsg {
+            name: "tier".to_owned(),
+            kind: IndexValueKind::String,
+            description: None,
+            writable_by_contracts: false,
+        };
+        exec_set_index(ctx(deps.as_mut()), index).unwrap();
+        let tier = IndexUpdate {
+            name: "tier".to_owned(),
+            value: IndexValue::String("gold".to_owned()),
+        };
+        register(deps.as_mut(), "a", "creator", vec![tier], vec![], vec![]);
+        register(deps.as_mut(), "b", "creator", vec![], vec![], vec![]);
+        register(deps.as_mut(), "c", "creator", vec![], vec![], vec![]);
+
+        let template = |json: &str| MigrationParams {
+            migrate_msg: Some(Binary::from(json.as_bytes())),
+            migrate_msg_overrides: Some(vec![MigrateMsgOverride {
+                contract: Addr::unchecked("c"),
+                migrate_msg: Binary::from(br#"{"id":"{{contract_id}}"}"#),
+            }]),
+            migrate_msg_template: Some(true),
+            ..migration_params()
+        };
+
+        // Unknown placeholders and indices are rejected up front
+        let session_name = SESSION.to_owned();
+        for json in [
+            r#"{"x":"{{admin}}"}"#,
+            r#"{"x":"{{index:color}}"}"#,
+            r#"{"x":"{{name"}"#,
+        ] {
+            exec_begin_migration(ctx(deps.as_mut()), template(json)).unwrap_err();
+            assert!(!MIGRATIONS.has(deps.as_ref().storage, &session_name));
+        }
+
+        let json = r#"{"addr":"{{contract_address}}","id":"{{contract_id}}","tier":"{{index:tier}}","label":"{{name}}/{{index:tier}}","n":[1]}"#;
+        exec_begin_migration(ctx(deps.as_mut()), template(json)).unwrap();
+        let resp = exec_step_migration(ctx(deps.as_mut()), SESSION.to_owned()).unwrap();
+        let msgs: Vec<Value> = resp
+            .messages
+            .iter()
+            .map(|submsg| match &submsg.msg {
+                CosmosMsg::Wasm(WasmMsg::Migrate { msg, .. }) => from_json(msg).unwrap(),
+                _ => panic!("expected a migrate msg"),
+            })
+            .collect();
+
+        // Lone placeholders keep their JSON type and missing values are null,
+        // while overrides replace the template for their contract
+        assert_eq!(
+            msgs,
+            vec![
+                serde_json::json!({"addr": "a", "id": 0, "tier": "gold", "label": "/gold", "n": [1]}),
+                serde_json::json!({"addr": "b", "id": 1, "tier": null, "label": "/", "n": [1]}),
+                serde_json::json!({"id": 2}),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_history_of_sessions() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), default_config());
+        register(deps.as_mut(), "a", "creator", vec![], vec![], vec![]);
+        register(deps.as_mut(), "b", "creator", vec![], vec![], vec![]);
+        let session_name = SESSION.to_owned();
+
+        exec_begin_migration(ctx(deps.as_mut()), migration_params()).unwrap();
+        assert_eq!(
+            MIGRATION_NAME_2_SESSION_ID
+                .load(deps.as_ref().storage, &session_name)
+                .unwrap(),
+            0
+        );
+        let record = MIGRATION_HISTORY.load(deps.as_ref().storage, 0).unwrap();
+        assert_eq!(
+            (record.status, record.ended_at_height),
+            (MigrationStatus::Running, None)
+        );
+
+        // A running session's name can't be reused
+        let err = exec_begin_migration(ctx(deps.as_mut()), migration_params()).unwrap_err();
+        assert!(matches!(err, ContractError::MigrationExists { .. }));
+
+        let resp = exec_step_migration(ctx(deps.as_mut()), SESSION.to_owned()).unwrap();
+        for submsg in resp.messages.iter() {
+            // Contract "a" migrates successfully while "b" fails
+            let (_, addr, _) = reply_to(submsg, true);
+            let (reply, ..) = reply_to(submsg, addr == "a");
+            handle_migration_reply(deps.as_mut(), mock_env(), reply).unwrap();
+        }
+        let record = MIGRATION_HISTORY.load(deps.as_ref().storage, 0).unwrap();
+        assert_eq!((record.n_success, record.n_error), (1, 1));
+        assert_eq!(record.status, MigrationStatus::Complete);
+        assert_eq!(record.ended_at_height, Some(mock_env().block.height));
+        let results: Vec<MigrationResult> = IX_MIGRATION_RESULTS_BY_SESSION
+            .prefix(0)
+            .keys(deps.as_ref().storage, None, None, Order::Ascending)
+            .map(|k| MIGRATION_RESULTS.load(deps.as_ref().storage, k.unwrap()).unwrap())
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().any(|x| x.contract == "a" && x.error.is_none()));
+        assert!(results.iter().any(|x| x.contract == "b" && x.error.is_some()));
+
+        // Nor can a finished session's while it can still be rolled back
+        exec_begin_migration(ctx(deps.as_mut()), migration_params()).unwrap_err();
+        assert_eq!(
+            MIGRATIONS.load(deps.as_ref().storage, &session_name).unwrap().status,
+            MigrationStatus::Complete
+        );
+
+        // Cancelling discards the session but not its history
+        exec_cancel_migration(ctx(deps.as_mut()), SESSION.to_owned()).unwrap();
+        assert!(!MIGRATIONS.has(deps.as_ref().storage, &session_name));
+        assert!(MIGRATION_PREV_CODE_IDS
+            .prefix(&session_name)
+            .is_empty(deps.as_ref().storage));
+        assert!(MIGRATION_ERRORS.prefix(&session_name).is_empty(deps.as_ref().storage));
+        assert_eq!(
+            MIGRATION_HISTORY.load(deps.as_ref().storage, 0).unwrap().status,
+            MigrationStatus::Cancelled
+        );
+
+        exec_begin_migration(ctx(deps.as_mut()), migration_params()).unwrap();
+        assert_eq!(
+            MIGRATION_NAME_2_SESSION_ID
+                .load(deps.as_ref().storage, &session_name)
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            MIGRATION_HISTORY.load(deps.as_ref().storage, 0).unwrap().status,
+            MigrationStatus::Cancelled
+        );
+        assert_eq!(
+            MIGRATION_HISTORY.load(deps.as_ref().storage, 1).unwrap().status,
+            MigrationStatus::Running
+        );
+    }
+}
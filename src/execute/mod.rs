@@ -1,7 +1,17 @@
+pub mod code_ids;
 pub mod create;
+pub mod creators;
+pub mod deregister;
 pub mod hide;
+pub mod import;
+pub mod manager;
 pub mod migrate;
+pub mod pause;
+pub mod quotas;
+pub mod receive;
+pub mod roles;
 pub mod set_config;
+pub mod set_index;
 pub mod set_preset;
 pub mod update;
 
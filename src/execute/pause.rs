@@ -0,0 +1,133 @@
+use crate::{
+    error::ContractError,
+    state::{models::PauseState, storage::PAUSE_STATE},
+};
+use cosmwasm_std::{attr, Event, Response};
+
+use super::Context;
+
+/// Pause the given functions of the factory, replacing any previous pause.
+pub fn exec_pause(
+    ctx: Context,
+    pause: PauseState,
+) -> Result<Response, ContractError> {
+    let Context { deps, .. } = ctx;
+
+    if pause.switches.is_empty() {
+        return Err(ContractError::ValidationError {
+            reason: "nothing to pause".to_owned(),
+        });
+    }
+
+    PAUSE_STATE.save(deps.storage, &pause)?;
+
+    let switches: Vec<String> = pause.switches.iter().map(|x| format!("{:?}", x)).collect();
+
+    Ok(Response::new().add_attributes(vec![attr("action", "pause")]).add_event(
+        Event::new("factory-pause").add_attributes(vec![
+            attr("switches", switches.join(",")),
+            attr("reason", pause.reason.unwrap_or_default()),
+        ]),
+    ))
+}
+
+pub fn exec_unpause(ctx: Context) -> Result<Response, ContractError> {
+    let Context { deps, .. } = ctx;
+
+    PAUSE_STATE.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "unpause")])
+        .add_event(Event::new("factory-unpause")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        contract::execute,
+        msg::{ExecuteMsg, PresetsExecuteMsg, SetPresetMsg},
+        state::{models::PauseSwitch, storage::PRESETS},
+        testing::{default_config, instantiate, MANAGER},
+        util::ensure_not_paused,
+    };
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    fn set_preset(name: &str) -> ExecuteMsg {
+        ExecuteMsg::Presets(PresetsExecuteMsg::Set(SetPresetMsg {
+            name: name.to_owned(),
+            values: Default::default(),
+            overridable: false,
+        }))
+    }
+
+    fn is_paused_err(err: &ContractError) -> bool {
+        matches!(err, ContractError::NotAuthorized { reason } if reason.contains("paused"))
+    }
+
+    #[test]
+    fn pauses_switches_independently() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), default_config());
+        let pause = PauseState {
+            switches: vec![PauseSwitch::Create, PauseSwitch::Presets],
+            reason: Some("maintenance".to_owned()),
+        };
+
+        // Only the manager may pause
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::Pause(pause.clone()),
+        )
+        .unwrap_err();
+        assert!(!PAUSE_STATE.exists(deps.as_ref().storage));
+
+        let nothing = PauseState {
+            switches: vec![],
+            reason: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MANAGER, &[]),
+            ExecuteMsg::Pause(nothing),
+        )
+        .unwrap_err();
+        assert!(!PAUSE_STATE.exists(deps.as_ref().storage));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MANAGER, &[]),
+            ExecuteMsg::Pause(pause.clone()),
+        )
+        .unwrap();
+        assert_eq!(PAUSE_STATE.load(deps.as_ref().storage).unwrap(), pause);
+
+        // Paused functions are rejected, even for the manager, while others
+        // remain available
+        let err = execute(deps.as_mut(), mock_env(), mock_info(MANAGER, &[]), set_preset("a")).unwrap_err();
+        assert!(is_paused_err(&err));
+        assert!(!PRESETS.has(deps.as_ref().storage, &"a".to_owned()));
+        assert!(is_paused_err(
+            &ensure_not_paused(deps.as_ref().storage, PauseSwitch::Create).unwrap_err()
+        ));
+        ensure_not_paused(deps.as_ref().storage, PauseSwitch::Update).unwrap();
+        ensure_not_paused(deps.as_ref().storage, PauseSwitch::Migrations).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MANAGER, &[]),
+            ExecuteMsg::Unpause {},
+        )
+        .unwrap();
+        assert!(!PAUSE_STATE.exists(deps.as_ref().storage));
+
+        execute(deps.as_mut(), mock_env(), mock_info(MANAGER, &[]), set_preset("a")).unwrap();
+        assert!(PRESETS.has(deps.as_ref().storage, &"a".to_owned()));
+        ensure_not_paused(deps.as_ref().storage, PauseSwitch::Create).unwrap();
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{
+    error::ContractError,
+    math::add_u32,
+    state::{
+        models::CreationLimits,
+        storage::{CONFIG_CREATION_LIMITS, CREATOR_N_CREATED, CREATOR_QUOTA_EXEMPTIONS, CREATOR_WINDOWS},
+    },
+    util::{is_exempt, load_n_created, load_window},
+};
+use cosmwasm_std::{attr, Addr, Response, Storage, Timestamp};
+
+use super::Context;
+
+pub fn exec_exempt_creator(
+    ctx: Context,
+    creator: Addr,
+) -> Result<Response, ContractError> {
+    let Context { deps, .. } = ctx;
+    let creator = deps.api.addr_validate(creator.as_str())?;
+
+    CREATOR_QUOTA_EXEMPTIONS.save(deps.storage, &creator, &true)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "exempt_creator"),
+        attr("creator", creator.to_string()),
+    ]))
+}
+
+pub fn exec_unexempt_creator(
+    ctx: Context,
+    creator: Addr,
+) -> Result<Response, ContractError> {
+    let Context { deps, .. } = ctx;
+    let creator = deps.api.addr_validate(creator.as_str())?;
+
+    CREATOR_QUOTA_EXEMPTIONS.remove(deps.storage, &creator);
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "unexempt_creator"),
+        attr("creator", creator.to_string()),
+    ]))
+}
+
+/// Count a creator's request to create `n` contracts against its quotas,
+/// erroring if doing so would exceed them.
+pub fn consume_creator_quota(
+    store: &mut dyn Storage,
+    time: Timestamp,
+    creator: &Addr,
+    n: u32,
+) -> Result<(), ContractError> {
+    let n_created = add_u32(load_n_created(store, creator)?, n)?;
+
+    let limits = if is_exempt(store, creator)? {
+        None
+    } else {
+        CONFIG_CREATION_LIMITS.may_load(store)?
+    };
+
+    if let Some(CreationLimits { lifetime, window }) = limits {
+        if let Some(max) = lifetime {
+            if n_created > max {
+                return Err(ContractError::NotAuthorized {
+                    reason: format!("creator has reached its limit of {} contracts", max),
+                });
+            }
+        }
+
+        if let Some(limit) = window {
+            let mut window = load_window(store, time, creator, &limit)?;
+            window.n_created = add_u32(window.n_created, n)?;
+            if window.n_created > limit.max {
+                return Err(ContractError::NotAuthorized {
+                    reason: format!(
+                        "creator has reached its limit of {} contracts per {} seconds",
+                        limit.max, limit.seconds
+                    ),
+                });
+            }
+            CREATOR_WINDOWS.save(store, creator, &window)?;
+        }
+    }
+
+    // Creations are counted even without limits, which then also apply to
+    // contracts created before they were configured.
+    CREATOR_N_CREATED.save(store, creator, &n_created)?;
+
+    Ok(())
+}
+
+/// Give back quota consumed for contracts that ended up not being created.
+pub fn release_creator_quota(
+    store: &mut dyn Storage,
+    time: Timestamp,
+    creator: &Addr,
+    n: u32,
+) -> Result<(), ContractError> {
+    let n_created = load_n_created(store, creator)?;
+    CREATOR_N_CREATED.save(store, creator, &n_created.saturating_sub(n))?;
+
+    let limit = match CONFIG_CREATION_LIMITS.may_load(store)?.and_then(|x| x.window) {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+
+    if is_exempt(store, creator)? {
+        return Ok(());
+    }
+
+    let mut window = load_window(store, time, creator, &limit)?;
+    window.n_created = window.n_created.saturating_sub(n);
+    CREATOR_WINDOWS.save(store, creator, &window)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        state::models::CreationWindowLimit,
+        testing::{default_config, instantiate, MANAGER},
+    };
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_env, mock_info},
+        DepsMut,
+    };
+
+    fn instantiate_with_limits(
+        deps: DepsMut,
+        limits: CreationLimits,
+    ) {
+        let mut config = default_config();
+        config.creation_limits = Some(limits);
+        instantiate(deps, config);
+    }
+
+    #[test]
+    fn lifetime_limit_counts_every_creation() {
+        let mut deps = mock_dependencies();
+        let creator = Addr::unchecked("creator");
+        let time = mock_env().block.time;
+        instantiate_with_limits(
+            deps.as_mut(),
+            CreationLimits {
+                lifetime: Some(2),
+                window: None,
+            },
+        );
+
+        consume_creator_quota(deps.as_mut().storage, time, &creator, 2).unwrap();
+        assert_eq!(load_n_created(&deps.storage, &creator).unwrap(), 2);
+
+        let err = consume_creator_quota(deps.as_mut().storage, time, &creator, 1).unwrap_err();
+        assert!(matches!(err, ContractError::NotAuthorized { .. }));
+
+        // Contracts that failed to be created don't count
+        release_creator_quota(deps.as_mut().storage, time, &creator, 1).unwrap();
+        assert_eq!(load_n_created(&deps.storage, &creator).unwrap(), 1);
+        consume_creator_quota(deps.as_mut().storage, time, &creator, 1).unwrap();
+    }
+
+    #[test]
+    fn window_limit_resets_after_window() {
+        let mut deps = mock_dependencies();
+        let creator = Addr::unchecked("creator");
+        let time = mock_env().block.time;
+        instantiate_with_limits(
+            deps.as_mut(),
+            CreationLimits {
+                lifetime: None,
+                window: Some(CreationWindowLimit { seconds: 60, max: 1 }),
+            },
+        );
+
+        consume_creator_quota(deps.as_mut().storage, time, &creator, 1).unwrap();
+        let err = consume_creator_quota(deps.as_mut().storage, time.plus_seconds(59), &creator, 1).unwrap_err();
+        assert!(matches!(err, ContractError::NotAuthorized { .. }));
+
+        consume_creator_quota(deps.as_mut().storage, time.plus_seconds(60), &creator, 1).unwrap();
+        let window = CREATOR_WINDOWS.load(&deps.storage, &creator).unwrap();
+        assert_eq!(window.started_at, time.plus_seconds(60));
+        assert_eq!(window.n_created, 1);
+    }
+
+    #[test]
+    fn exempt_creators_and_manager_bypass_limits() {
+        let mut deps = mock_dependencies();
+        let time = mock_env().block.time;
+        instantiate_with_limits(
+            deps.as_mut(),
+            CreationLimits {
+                lifetime: Some(1),
+                window: None,
+            },
+        );
+
+        let manager = Addr::unchecked(MANAGER);
+        consume_creator_quota(deps.as_mut().storage, time, &manager, 5).unwrap();
+
+        let creator = Addr::unchecked("creator");
+        let ctx = Context {
+            deps: deps.as_mut(),
+            env: mock_env(),
+            info: mock_info(MANAGER, &[]),
+        };
+        exec_exempt_creator(ctx, creator.to_owned()).unwrap();
+        consume_creator_quota(deps.as_mut().storage, time, &creator, 5).unwrap();
+
+        let ctx = Context {
+            deps: deps.as_mut(),
+            env: mock_env(),
+            info: mock_info(MANAGER, &[]),
+        };
+        exec_unexempt_creator(ctx, creator.to_owned()).unwrap();
+        assert!(!CREATOR_QUOTA_EXEMPTIONS.has(&deps.storage, &creator));
+        let err = consume_creator_quota(deps.as_mut().storage, time, &creator, 1).unwrap_err();
+        assert!(matches!(err, ContractError::NotAuthorized { .. }));
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    error::ContractError,
+    msg::ReceiveMsg,
+    state::storage::{CONFIG_CODE_ID_FEES, CONFIG_PRESET_FEES},
+};
+use cosmwasm_std::{from_json, Addr, MessageInfo, Order, Response, Storage};
+use cw20::{Cw20CoinVerified, Cw20ReceiveMsg, Denom};
+
+use super::{
+    create::{exec_create, exec_create_batch},
+    Context,
+};
+
+/// Execute a message sent along with CW20 tokens on behalf of the tokens'
+/// sender, using the tokens to pay creation fees.
+pub fn exec_receive(
+    ctx: Context,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let Context { deps, env, info } = ctx;
+    let Cw20ReceiveMsg { sender, amount, msg } = msg;
+
+    // The sender of the message is the CW20 token contract. Only tokens that
+    // creation fees are paid in are trusted to report who sent the tokens.
+    if !is_fee_token(deps.storage, &info.sender)? {
+        return Err(ContractError::NotAuthorized {
+            reason: format!("{} is not a creation fee token", info.sender),
+        });
+    }
+
+    let cw20 = Cw20CoinVerified {
+        address: info.sender,
+        amount,
+    };
+
+    let info = MessageInfo {
+        sender: deps.api.addr_validate(&sender)?,
+        funds: info.funds,
+    };
+
+    let ctx = Context { deps, env, info };
+
+    match from_json::<ReceiveMsg>(&msg)? {
+        ReceiveMsg::Create(msg) => exec_create(ctx, msg, Some(cw20)),
+        ReceiveMsg::CreateBatch(msg) => exec_create_batch(ctx, msg, Some(cw20)),
+    }
+}
+
+/// Is the given address a CW20 token in which any creation fee is paid?
+fn is_fee_token(
+    store: &dyn Storage,
+    address: &Addr,
+) -> Result<bool, ContractError> {
+    let fees = CONFIG_CODE_ID_FEES
+        .range(store, None, None, Order::Ascending)
+        .map(|r| r.map(|(_, fee)| fee))
+        .chain(
+            CONFIG_PRESET_FEES
+                .range(store, None, None, Order::Ascending)
+                .map(|r| r.map(|(_, fee)| fee)),
+        );
+    for fee in fees {
+        if matches!(fee?.denom, Denom::Cw20(token) if token == *address) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        msg::CreateMsg,
+        state::{models::CreationFee, storage::MANAGED_BY},
+    };
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_env, mock_info},
+        to_json_binary, Uint128, Uint64,
+    };
+
+    fn receive_create_msg(sender: &str) -> Cw20ReceiveMsg {
+        Cw20ReceiveMsg {
+            sender: sender.to_owned(),
+            amount: Uint128::new(100),
+            msg: to_json_binary(&ReceiveMsg::Create(CreateMsg {
+                preset: None,
+                code_id: Some(Uint64::new(1)),
+                channel: None,
+                instantiate_msg: Default::default(),
+                name: None,
+                label: "victim".to_owned(),
+                admin: None,
+                indices: None,
+                relations: None,
+                tags: None,
+                salt: None,
+                funds: None,
+                callback: None,
+            }))
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn rejects_receive_from_non_fee_token() {
+        let mut deps = mock_dependencies();
+        MANAGED_BY
+            .save(deps.as_mut().storage, &Addr::unchecked("manager"))
+            .unwrap();
+        CONFIG_CODE_ID_FEES
+            .save(
+                deps.as_mut().storage,
+                1,
+                &CreationFee {
+                    denom: Denom::Cw20(Addr::unchecked("token")),
+                    amount: Uint128::new(100),
+                },
+            )
+            .unwrap();
+
+        let ctx = Context {
+            deps: deps.as_mut(),
+            env: mock_env(),
+            info: mock_info("fake_token", &[]),
+        };
+        let err = exec_receive(ctx, receive_create_msg("manager")).unwrap_err();
+
+        assert!(matches!(err, ContractError::NotAuthorized { .. }));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    error::ContractError,
+    state::{models::Role, storage::ROLE_MEMBERS},
+};
+use cosmwasm_std::{attr, Addr, Event, Response};
+
+use super::Context;
+
+pub fn exec_grant_role(
+    ctx: Context,
+    role: Role,
+    address: Addr,
+) -> Result<Response, ContractError> {
+    let Context { deps, .. } = ctx;
+    let address = deps.api.addr_validate(address.as_str())?;
+
+    ROLE_MEMBERS.save(deps.storage, (role.as_str(), &address), &0)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "grant_role")])
+        .add_event(
+            Event::new("factory-role-grant")
+                .add_attributes(vec![attr("role", role.as_str()), attr("address", address.to_string())]),
+        ))
+}
+
+pub fn exec_revoke_role(
+    ctx: Context,
+    role: Role,
+    address: Addr,
+) -> Result<Response, ContractError> {
+    let Context { deps, .. } = ctx;
+
+    if !ROLE_MEMBERS.has(deps.storage, (role.as_str(), &address)) {
+        return Err(ContractError::ValidationError {
+            reason: format!("{} does not have the {} role", address, role.as_str()),
+        });
+    }
+
+    ROLE_MEMBERS.remove(deps.storage, (role.as_str(), &address));
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "revoke_role")])
+        .add_event(
+            Event::new("factory-role-revoke")
+                .add_attributes(vec![attr("role", role.as_str()), attr("address", address.to_string())]),
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        contract::execute,
+        msg::{ExecuteMsg, QuotasExecuteMsg, RolesExecuteMsg},
+        state::storage::CREATOR_QUOTA_EXEMPTIONS,
+        testing::{default_config, instantiate, MANAGER},
+    };
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_env, mock_info},
+        DepsMut,
+    };
+
+    fn execute_as(
+        deps: DepsMut,
+        sender: &str,
+        msg: ExecuteMsg,
+    ) -> Result<Response, ContractError> {
+        execute(deps, mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    fn exempt(creator: &str) -> ExecuteMsg {
+        ExecuteMsg::Quotas(QuotasExecuteMsg::Exempt {
+            creator: Addr::unchecked(creator),
+        })
+    }
+
+    fn grant(
+        role: Role,
+        address: &str,
+    ) -> ExecuteMsg {
+        ExecuteMsg::Roles(RolesExecuteMsg::Grant {
+            role,
+            address: Addr::unchecked(address),
+        })
+    }
+
+    #[test]
+    fn roles_gate_handlers() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), default_config());
+        let admin = Addr::unchecked("admin");
+
+        // Only the manager may grant roles
+        let err = execute_as(deps.as_mut(), "admin", grant(Role::ConfigAdmin, "admin")).unwrap_err();
+        assert!(matches!(err, ContractError::NotAuthorized { .. }));
+        assert!(!ROLE_MEMBERS.has(deps.as_ref().storage, (Role::ConfigAdmin.as_str(), &admin)));
+
+        let err = execute_as(deps.as_mut(), "admin", exempt("alice")).unwrap_err();
+        assert!(matches!(err, ContractError::NotAuthorized { .. }));
+        assert!(!CREATOR_QUOTA_EXEMPTIONS.has(deps.as_ref().storage, &Addr::unchecked("alice")));
+
+        execute_as(deps.as_mut(), MANAGER, grant(Role::ConfigAdmin, "admin")).unwrap();
+        assert!(ROLE_MEMBERS.has(deps.as_ref().storage, (Role::ConfigAdmin.as_str(), &admin)));
+
+        // A role grants access only to the handlers it covers
+        execute_as(deps.as_mut(), "admin", exempt("alice")).unwrap();
+        assert!(CREATOR_QUOTA_EXEMPTIONS.has(deps.as_ref().storage, &Addr::unchecked("alice")));
+        let err = execute_as(deps.as_mut(), "admin", grant(Role::Migrator, "admin")).unwrap_err();
+        assert!(matches!(err, ContractError::NotAuthorized { .. }));
+        assert!(!ROLE_MEMBERS.has(deps.as_ref().storage, (Role::Migrator.as_str(), &admin)));
+
+        let revoke = ExecuteMsg::Roles(RolesExecuteMsg::Revoke {
+            role: Role::ConfigAdmin,
+            address: admin.clone(),
+        });
+        execute_as(deps.as_mut(), MANAGER, revoke.clone()).unwrap();
+        assert!(!ROLE_MEMBERS.has(deps.as_ref().storage, (Role::ConfigAdmin.as_str(), &admin)));
+        execute_as(deps.as_mut(), MANAGER, revoke).unwrap_err();
+
+        let err = execute_as(deps.as_mut(), "admin", exempt("bob")).unwrap_err();
+        assert!(matches!(err, ContractError::NotAuthorized { .. }));
+        assert!(!CREATOR_QUOTA_EXEMPTIONS.has(deps.as_ref().storage, &Addr::unchecked("bob")));
+    }
+}
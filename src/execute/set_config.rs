@@ -1,49 +1,417 @@
 use crate::{
     error::ContractError,
+    msg::ConfigureMsg,
     state::{
-        models::Config,
-        storage::{CONFIG_ALLOWED_CODE_IDS, CONFIG_DEFAULT_CODE_ID, MANAGED_BY},
+        models::{CodeIdFee, Config, ConfigChange, CreationFee, CreationLimits, PendingConfig, PresetFee},
+        storage::{
+            CONFIG_ALLOWED_CODE_IDS, CONFIG_CODE_ID_FEES, CONFIG_CREATION_LIMITS, CONFIG_DEFAULT_CODE_ID, CONFIG_DELAY,
+            CONFIG_PRESET_FEES, CONFIG_TREASURY, MANAGED_BY, PENDING_CONFIG,
+        },
     },
+    util::{allow_code_id, ensure_code_id_unreferenced, ensure_code_id_unused_by_channels},
 };
-use cosmwasm_std::{attr, ensure_eq, Response};
+use cosmwasm_std::{attr, Addr, DepsMut, Event, Order, Response, StdResult, Storage, Timestamp, Uint64};
+use cw20::Denom;
 
-use super::Context;
+use super::{
+    code_ids::{allow_code_ids, disallow_code_ids, set_channel, set_default_code_id},
+    Context,
+};
 
+/// Update the config, either immediately or, given an execute-after time, by
+/// enqueuing it as the pending config. Only the latter is possible once a
+/// config delay is set.
 pub fn exec_set_config(
     ctx: Context,
-    config: Config,
+    msg: ConfigureMsg,
+) -> Result<Response, ContractError> {
+    let ConfigureMsg { config, execute_after } = msg;
+
+    // The manager can only be changed through a two-step transfer
+    if config.managed_by != MANAGED_BY.load(ctx.deps.storage)? {
+        return Err(ContractError::ValidationError {
+            reason: "managed_by can't be changed by Configure; propose a new manager instead".to_owned(),
+        });
+    }
+
+    if let Some(execute_after) = execute_after {
+        return enqueue_config_change(ctx, ConfigChange::Configure(config), execute_after, "set_config");
+    }
+
+    ensure_no_config_delay(ctx.deps.storage)?;
+    apply_config(ctx.deps, config)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "set_config")]))
+}
+
+/// Enqueue a config change as the pending config, to be applied once its
+/// execute-after time passes. This must be at least the config delay from now.
+pub fn enqueue_config_change(
+    ctx: Context,
+    change: ConfigChange,
+    execute_after: Timestamp,
+    action: &str,
 ) -> Result<Response, ContractError> {
-    let Context { deps, info, .. } = ctx;
+    let Context { deps, env, info } = ctx;
+
+    if execute_after <= env.block.time {
+        return Err(ContractError::ValidationError {
+            reason: "execute_after must be in the future".to_owned(),
+        });
+    }
+
+    let config_delay = CONFIG_DELAY.may_load(deps.storage)?.unwrap_or_default();
+    if execute_after < env.block.time.plus_seconds(config_delay) {
+        return Err(ContractError::ValidationError {
+            reason: format!("execute_after must be at least {} seconds from now", config_delay),
+        });
+    }
+
+    if PENDING_CONFIG.exists(deps.storage) {
+        return Err(ContractError::ValidationError {
+            reason: "a config change is already pending".to_owned(),
+        });
+    }
+
+    PENDING_CONFIG.save(
+        deps.storage,
+        &PendingConfig {
+            change,
+            execute_after,
+            proposed_by: info.sender,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", action)]).add_event(
+        Event::new("factory-config-pending").add_attributes(vec![attr("execute_after", execute_after.to_string())]),
+    ))
+}
+
+/// Error if a config delay is set, in which case config changes must be
+/// enqueued rather than applied immediately.
+pub fn ensure_no_config_delay(store: &dyn Storage) -> Result<(), ContractError> {
+    let config_delay = CONFIG_DELAY.may_load(store)?.unwrap_or_default();
+    if config_delay > 0 {
+        return Err(ContractError::NotAuthorized {
+            reason: format!(
+                "config changes must be enqueued with an execute_after at least {} seconds from now",
+                config_delay
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Apply the pending config once its execute-after time has passed. Anyone
+/// may do this.
+pub fn exec_apply_pending_config(ctx: Context) -> Result<Response, ContractError> {
+    let Context { deps, env, .. } = ctx;
+
+    let PendingConfig {
+        change, execute_after, ..
+    } = PENDING_CONFIG
+        .may_load(deps.storage)?
+        .ok_or_else(|| ContractError::ValidationError {
+            reason: "no config change pending".to_owned(),
+        })?;
+
+    if env.block.time < execute_after {
+        return Err(ContractError::NotAuthorized {
+            reason: format!("pending config can't be applied before {}", execute_after),
+        });
+    }
+
+    PENDING_CONFIG.remove(deps.storage);
+
+    match change {
+        ConfigChange::Configure(config) => apply_config(deps, config)?,
+        ConfigChange::AllowCodeIds { code_ids } => allow_code_ids(deps.storage, &code_ids)?,
+        ConfigChange::DisallowCodeIds { code_ids } => disallow_code_ids(deps.storage, &code_ids)?,
+        ConfigChange::SetDefaultCodeId { code_id } => set_default_code_id(deps.storage, code_id)?,
+        ConfigChange::SetChannel { name, code_id } => set_channel(deps.storage, &name, code_id)?,
+    }
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "apply_pending_config")])
+        .add_event(Event::new("factory-config-applied")))
+}
+
+pub fn exec_cancel_pending_config(ctx: Context) -> Result<Response, ContractError> {
+    let Context { deps, .. } = ctx;
+
+    if !PENDING_CONFIG.exists(deps.storage) {
+        return Err(ContractError::ValidationError {
+            reason: "no config change pending".to_owned(),
+        });
+    }
+
+    PENDING_CONFIG.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "cancel_pending_config")])
+        .add_event(Event::new("factory-config-cancelled")))
+}
+
+/// Replace the current config with the given one, except for the manager and
+/// pause state, which are changed by other means.
+fn apply_config(
+    mut deps: DepsMut,
+    config: Config,
+) -> Result<(), ContractError> {
     let Config {
         allowed_code_ids,
         default_code_id,
-        managed_by,
+        treasury,
+        code_id_fees,
+        preset_fees,
+        creation_limits,
+        config_delay,
+        ..
     } = config;
 
-    ensure_eq!(
-        info.sender,
-        MANAGED_BY.load(deps.storage)?,
-        ContractError::NotAuthorized {
-            reason: "only manager can update the factory config".to_owned()
+    // Replace allowed code IDs, if given, keeping the metadata of those that
+    // remain allowed
+    if let Some(allowed_code_ids) = allowed_code_ids {
+        let prev_default_code_id = CONFIG_DEFAULT_CODE_ID.may_load(deps.storage)?;
+        let disallowed_code_ids: Vec<Uint64> = CONFIG_ALLOWED_CODE_IDS
+            .keys(deps.storage, None, None, Order::Ascending)
+            .map(|r| r.map(Uint64::from))
+            .collect::<StdResult<Vec<_>>>()?
+            .into_iter()
+            .filter(|x| !allowed_code_ids.contains(x) && Some(*x) != default_code_id)
+            .collect();
+        for code_id in disallowed_code_ids {
+            // The current default code ID may be disallowed if the new config
+            // replaces it, but channels must never be left resolving to a
+            // disallowed code ID.
+            if prev_default_code_id == Some(code_id) && default_code_id != Some(code_id) {
+                ensure_code_id_unused_by_channels(deps.storage, code_id)?;
+            } else {
+                ensure_code_id_unreferenced(deps.storage, code_id)?;
+            }
+            CONFIG_ALLOWED_CODE_IDS.remove(deps.storage, code_id.u64());
+        }
+        for code_id in allowed_code_ids.iter() {
+            allow_code_id(deps.storage, *code_id)?;
         }
-    );
-
-    // Upsert manager address
-    MANAGED_BY.save(deps.storage, &deps.api.addr_validate(managed_by.as_str())?)?;
-
-    // Re-init allowed code IDs
-    CONFIG_ALLOWED_CODE_IDS.clear(deps.storage);
-    for code_id in allowed_code_ids.iter() {
-        CONFIG_ALLOWED_CODE_IDS.save(deps.storage, code_id.u64(), &0)?;
     }
 
     // Save or remove default code ID
     if let Some(default_code_id) = default_code_id {
-        CONFIG_ALLOWED_CODE_IDS.save(deps.storage, default_code_id.u64(), &0)?;
+        allow_code_id(deps.storage, default_code_id)?;
         CONFIG_DEFAULT_CODE_ID.save(deps.storage, &default_code_id)?;
     } else {
         CONFIG_DEFAULT_CODE_ID.remove(deps.storage);
     }
 
-    Ok(Response::new().add_attributes(vec![attr("action", "set_config")]))
+    // Re-init creation fees
+    CONFIG_CODE_ID_FEES.clear(deps.storage);
+    CONFIG_PRESET_FEES.clear(deps.storage);
+    save_creation_fees(deps.branch(), treasury, code_id_fees, preset_fees)?;
+    save_creation_limits(deps.branch(), creation_limits)?;
+
+    if let Some(config_delay) = config_delay {
+        CONFIG_DELAY.save(deps.storage, &config_delay)?;
+    } else {
+        CONFIG_DELAY.remove(deps.storage);
+    }
+
+    Ok(())
+}
+
+/// Save the treasury and creation fees of a config, validating each fee.
+pub fn save_creation_fees(
+    deps: DepsMut,
+    treasury: Option<Addr>,
+    code_id_fees: Option<Vec<CodeIdFee>>,
+    preset_fees: Option<Vec<PresetFee>>,
+) -> Result<(), ContractError> {
+    if let Some(treasury) = treasury {
+        CONFIG_TREASURY.save(deps.storage, &deps.api.addr_validate(treasury.as_str())?)?;
+    } else {
+        CONFIG_TREASURY.remove(deps.storage);
+    }
+
+    for CodeIdFee { code_id, fee } in code_id_fees.unwrap_or_default() {
+        let fee = validate_creation_fee(&deps, fee)?;
+        CONFIG_CODE_ID_FEES.save(deps.storage, code_id.u64(), &fee)?;
+    }
+
+    for PresetFee { preset, fee } in preset_fees.unwrap_or_default() {
+        let fee = validate_creation_fee(&deps, fee)?;
+        CONFIG_PRESET_FEES.save(deps.storage, &preset, &fee)?;
+    }
+
+    Ok(())
+}
+
+/// Save or remove limits on the number of contracts each creator may create.
+pub fn save_creation_limits(
+    deps: DepsMut,
+    creation_limits: Option<CreationLimits>,
+) -> Result<(), ContractError> {
+    if let Some(limits) = creation_limits {
+        if limits.window.as_ref().map(|x| x.seconds == 0).unwrap_or(false) {
+            return Err(ContractError::ValidationError {
+                reason: "creation limit window must be at least one second".to_owned(),
+            });
+        }
+        CONFIG_CREATION_LIMITS.save(deps.storage, &limits)?;
+    } else {
+        CONFIG_CREATION_LIMITS.remove(deps.storage);
+    }
+    Ok(())
+}
+
+fn validate_creation_fee(
+    deps: &DepsMut,
+    fee: CreationFee,
+) -> Result<CreationFee, ContractError> {
+    if fee.amount.is_zero() {
+        return Err(ContractError::ValidationError {
+            reason: "creation fee amount must be non-zero".to_owned(),
+        });
+    }
+    let denom = match fee.denom {
+        Denom::Native(denom) => {
+            if denom.is_empty() {
+                return Err(ContractError::ValidationError {
+                    reason: "creation fee denom must be non-empty".to_owned(),
+                });
+            }
+            Denom::Native(denom)
+        },
+        Denom::Cw20(address) => Denom::Cw20(deps.api.addr_validate(address.as_str())?),
+    };
+    Ok(CreationFee {
+        denom,
+        amount: fee.amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        execute::code_ids::exec_allow_code_ids,
+        state::storage::CONFIG_ALLOWED_CODE_IDS,
+        testing::{default_config, instantiate, MANAGER},
+    };
+    use cosmwasm_std::{
+        testing::{mock_dependencies, mock_env, mock_info},
+        Env,
+    };
+
+    const DELAY: u64 = 100;
+
+    fn ctx_at(
+        deps: DepsMut,
+        env: Env,
+    ) -> Context {
+        Context {
+            deps,
+            env,
+            info: mock_info(MANAGER, &[]),
+        }
+    }
+
+    fn env_after(seconds: u64) -> Env {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        env
+    }
+
+    fn delayed_config() -> Config {
+        Config {
+            config_delay: Some(DELAY),
+            ..default_config()
+        }
+    }
+
+    #[test]
+    fn config_delay_prevents_immediate_changes() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), default_config());
+
+        // Without a delay, the config is applied immediately
+        let msg = ConfigureMsg {
+            config: delayed_config(),
+            execute_after: None,
+        };
+        exec_set_config(ctx_at(deps.as_mut(), mock_env()), msg).unwrap();
+        assert_eq!(CONFIG_DELAY.load(deps.as_ref().storage).unwrap(), DELAY);
+
+        // Once set, neither Configure nor code ID changes apply immediately
+        let msg = ConfigureMsg {
+            config: default_config(),
+            execute_after: None,
+        };
+        exec_set_config(ctx_at(deps.as_mut(), mock_env()), msg).unwrap_err();
+        exec_allow_code_ids(ctx_at(deps.as_mut(), mock_env()), vec![Uint64::new(2)], None).unwrap_err();
+        assert!(!CONFIG_ALLOWED_CODE_IDS.has(deps.as_ref().storage, 2));
+
+        // Nor may they be enqueued to apply any sooner than the delay
+        let too_soon = mock_env().block.time.plus_seconds(DELAY - 1);
+        exec_allow_code_ids(ctx_at(deps.as_mut(), mock_env()), vec![Uint64::new(2)], Some(too_soon)).unwrap_err();
+        assert!(!PENDING_CONFIG.exists(deps.as_ref().storage));
+    }
+
+    #[test]
+    fn applies_pending_config_after_delay() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), delayed_config());
+
+        let execute_after = mock_env().block.time.plus_seconds(DELAY);
+        exec_allow_code_ids(
+            ctx_at(deps.as_mut(), mock_env()),
+            vec![Uint64::new(2)],
+            Some(execute_after),
+        )
+        .unwrap();
+        let pending = PENDING_CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(pending.execute_after, execute_after);
+        assert_eq!(pending.proposed_by, Addr::unchecked(MANAGER));
+
+        // Only one change may be pending at a time
+        exec_allow_code_ids(
+            ctx_at(deps.as_mut(), mock_env()),
+            vec![Uint64::new(3)],
+            Some(execute_after),
+        )
+        .unwrap_err();
+
+        // The change can't be applied early
+        exec_apply_pending_config(ctx_at(deps.as_mut(), env_after(DELAY - 1))).unwrap_err();
+        assert!(PENDING_CONFIG.exists(deps.as_ref().storage));
+        assert!(!CONFIG_ALLOWED_CODE_IDS.has(deps.as_ref().storage, 2));
+
+        exec_apply_pending_config(ctx_at(deps.as_mut(), env_after(DELAY))).unwrap();
+        assert!(!PENDING_CONFIG.exists(deps.as_ref().storage));
+        assert!(CONFIG_ALLOWED_CODE_IDS.has(deps.as_ref().storage, 2));
+        assert!(!CONFIG_ALLOWED_CODE_IDS.has(deps.as_ref().storage, 3));
+
+        // Nothing is left to apply
+        exec_apply_pending_config(ctx_at(deps.as_mut(), env_after(DELAY))).unwrap_err();
+    }
+
+    #[test]
+    fn cancels_pending_config() {
+        let mut deps = mock_dependencies();
+        instantiate(deps.as_mut(), delayed_config());
+
+        let msg = ConfigureMsg {
+            config: default_config(),
+            execute_after: Some(mock_env().block.time.plus_seconds(DELAY)),
+        };
+        exec_set_config(ctx_at(deps.as_mut(), mock_env()), msg).unwrap();
+        assert!(PENDING_CONFIG.exists(deps.as_ref().storage));
+
+        exec_cancel_pending_config(ctx_at(deps.as_mut(), mock_env())).unwrap();
+        assert!(!PENDING_CONFIG.exists(deps.as_ref().storage));
+
+        // The cancelled change is never applied, so the delay remains
+        exec_apply_pending_config(ctx_at(deps.as_mut(), env_after(DELAY))).unwrap_err();
+        assert_eq!(CONFIG_DELAY.load(deps.as_ref().storage).unwrap(), DELAY);
+        exec_cancel_pending_config(ctx_at(deps.as_mut(), mock_env())).unwrap_err();
+    }
 }
@@ -0,0 +1,86 @@
+use crate::{
+    error::ContractError,
+    msg::{SetIndexMsg, MAX_SIZEOF_STRING_KEY},
+    state::{
+        build_reverse_mapping_storage_key,
+        models::CustomIndex,
+        storage::{ContractId, CONFIG_CUSTOM_INDICES},
+    },
+};
+use cosmwasm_std::{attr, Order, Response, Storage};
+use cw_storage_plus::Map;
+
+use super::Context;
+
+/// Register a custom index or update an existing one. The value kind of an
+/// index can only change while no contract has a value in it.
+pub fn exec_set_index(
+    ctx: Context,
+    msg: SetIndexMsg,
+) -> Result<Response, ContractError> {
+    let Context { deps, .. } = ctx;
+    let SetIndexMsg {
+        name,
+        kind,
+        description,
+        writable_by_contracts,
+    } = msg;
+
+    if name.is_empty() || name.len() > MAX_SIZEOF_STRING_KEY {
+        return Err(ContractError::ValidationError {
+            reason: format!("index name must be 1 to {} bytes long", MAX_SIZEOF_STRING_KEY),
+        });
+    }
+
+    if let Some(index) = CONFIG_CUSTOM_INDICES.may_load(deps.storage, &name)? {
+        if index.kind != kind && !is_index_empty(deps.storage, &name) {
+            return Err(ContractError::ValidationError {
+                reason: format!("cannot change the value kind of non-empty index '{}'", name),
+            });
+        }
+    }
+
+    CONFIG_CUSTOM_INDICES.save(
+        deps.storage,
+        &name,
+        &CustomIndex {
+            kind,
+            description,
+            writable_by_contracts,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "set_index"), attr("index", name)]))
+}
+
+/// Unregister a custom index, which must not contain any values.
+pub fn exec_remove_index(
+    ctx: Context,
+    name: String,
+) -> Result<Response, ContractError> {
+    let Context { deps, .. } = ctx;
+
+    if !is_index_empty(deps.storage, &name) {
+        return Err(ContractError::ValidationError {
+            reason: format!("cannot remove non-empty index '{}'", name),
+        });
+    }
+
+    CONFIG_CUSTOM_INDICES.remove(deps.storage, &name);
+
+    Ok(Response::new().add_attributes(vec![attr("action", "remove_index"), attr("index", name)]))
+}
+
+/// An index is empty when no contract has a value in its reverse-lookup map.
+/// Unlike the index itself, this map retains values of hidden contracts.
+fn is_index_empty(
+    store: &dyn Storage,
+    name: &String,
+) -> bool {
+    let reverse_mapping_storage_key = build_reverse_mapping_storage_key(name);
+    let reverse_map: Map<ContractId, Vec<u8>> = Map::new(&reverse_mapping_storage_key);
+    reverse_map
+        .keys_raw(store, None, None, Order::Ascending)
+        .next()
+        .is_none()
+}
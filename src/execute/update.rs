@@ -1,15 +1,17 @@
 use crate::{
     error::ContractError,
-    msg::{ContractSelector, IndexUpdate, IndexValue, RelationUpdate, TagUpdate, UpdateMsg, UpdateOperation},
+    msg::{
+        ContractSelector, IndexUpdate, IndexValue, IndexValueKind, RelationUpdate, TagUpdate, UpdateMsg,
+        UpdateOperation, MAX_SIZEOF_BYTES_KEY, MAX_SIZEOF_STRING_KEY,
+    },
     state::{
         build_index_storage_key, build_reverse_mapping_storage_key,
         storage::{
-            ContractId, IndexMap, CONTRACT_ADDR_2_ID, CONTRACT_CUSTOM_IX_VALUES, CONTRACT_ID_2_ADDR,
-            CONTRACT_NAME_2_ID, CONTRACT_TAG_WEIGHTS, ID_2_UPDATED_AT, IX_REL_ADDR, IX_REL_CONTRACT_ADDR, IX_TAG,
-            IX_UPDATED_AT, IX_WEIGHTED_TAG,
+            ContractId, IndexMap, CONFIG_CUSTOM_INDICES, CONTRACT_ADDR_2_ID, CONTRACT_CUSTOM_IX_VALUES,
+            CONTRACT_ID_2_ADDR, CONTRACT_ID_2_IS_DEREGISTERING, CONTRACT_NAME_2_ID, CONTRACT_TAG_WEIGHTS,
+            ID_2_UPDATED_AT, IX_REL_ADDR, IX_REL_CONTRACT_ADDR, IX_TAG, IX_UPDATED_AT, IX_WEIGHTED_TAG, MANAGED_BY,
         },
     },
-    util::ensure_is_manager,
 };
 use cosmwasm_std::{attr, Addr, Response, Storage};
 use cw_storage_plus::Map;
@@ -29,10 +31,11 @@ pub fn exec_update(
     } = msg;
 
     // Get ID of contract applying updates. Sender must be either the
-    // contract itself, assuming it is managed by this factory, or the factory
-    // manager. No one else.
+    // contract itself, assuming it is managed by this factory, or a moderator,
+    // which is checked before dispatching here. No one else. Only the manager
+    // may write to indices that aren't writable by contracts.
+    let is_manager = MANAGED_BY.load(deps.storage)? == info.sender;
     let contract_id = if let Some(selector) = maybe_contract_selector {
-        ensure_is_manager(deps.storage, &info.sender)?;
         match selector {
             ContractSelector::Address(addr) => {
                 CONTRACT_ADDR_2_ID.load(deps.storage, &deps.api.addr_validate(addr.as_str())?)?
@@ -52,6 +55,12 @@ pub fn exec_update(
         CONTRACT_ADDR_2_ID.load(deps.storage, &info.sender)?
     };
 
+    if CONTRACT_ID_2_IS_DEREGISTERING.has(deps.storage, contract_id) {
+        return Err(ContractError::NotAuthorized {
+            reason: "contract is being deregistered".to_owned(),
+        });
+    }
+
     // Update the contract's entry in the updated_at index
     {
         // update entry in updated_at index
@@ -60,21 +69,43 @@ pub fn exec_update(
         }
 
         // insert updated values in index and the reverse lookup map
-        let t = env.block.time.nanos().to_le_bytes();
+        let t = IndexValue::Uint64(env.block.time.nanos().into()).to_bytes();
         IX_UPDATED_AT.save(deps.storage, (&t, contract_id), &0)?;
-        ID_2_UPDATED_AT.save(deps.storage, contract_id, &t.to_vec())?;
+        ID_2_UPDATED_AT.save(deps.storage, contract_id, &t)?;
     }
 
+    apply_updates(
+        deps.storage,
+        contract_id,
+        is_manager,
+        &index_updates.unwrap_or_default(),
+        &tag_updates.unwrap_or_default(),
+        &relation_updates.unwrap_or_default(),
+    )?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "update")]))
+}
+
+/// Apply custom index, tag and relation updates to a contract. Contracts may
+/// only write to custom indices that allow it, unlike the manager.
+pub fn apply_updates(
+    store: &mut dyn Storage,
+    contract_id: ContractId,
+    is_manager: bool,
+    index_updates: &[IndexUpdate],
+    tag_updates: &[TagUpdate],
+    relation_updates: &[RelationUpdate],
+) -> Result<(), ContractError> {
+    validate_updates(store, is_manager, index_updates, tag_updates, relation_updates)?;
+
     // Apply each index update
-    for IndexUpdate { name, value } in index_updates.unwrap_or_default().iter() {
+    for IndexUpdate { name, value } in index_updates.iter() {
         // Normalized received index value to u8 slice
         let bytes = value.to_bytes();
 
         // Track the fact that this index contains an entry for this contract so
-        // we can do things like
-        if !CONTRACT_CUSTOM_IX_VALUES.has(deps.storage, (contract_id, name)) {
-            CONTRACT_CUSTOM_IX_VALUES.save(deps.storage, (contract_id, name), &bytes)?;
-        }
+        // we can do things like hide it or re-key it later on.
+        CONTRACT_CUSTOM_IX_VALUES.save(store, (contract_id, name), &bytes)?;
 
         // Get index map
         let storage_key = build_index_storage_key(name);
@@ -85,24 +116,24 @@ pub fn exec_update(
         let reverse_map: Map<ContractId, Vec<u8>> = Map::new(&reverse_mapping_storage_key);
 
         // remove previous entry from index, which is now stale
-        if let Some(old_bytes) = reverse_map.may_load(deps.storage, contract_id)? {
-            map.remove(deps.storage, (old_bytes.as_slice(), contract_id));
+        if let Some(old_bytes) = reverse_map.may_load(store, contract_id)? {
+            map.remove(store, (old_bytes.as_slice(), contract_id));
         }
 
         // insert updated values in index and the reverse lookup map
-        map.save(deps.storage, (&bytes, contract_id), &0)?;
-        reverse_map.save(deps.storage, contract_id, &bytes)?;
+        map.save(store, (&bytes, contract_id), &0)?;
+        reverse_map.save(store, contract_id, &bytes)?;
     }
 
     // Update tags
-    for TagUpdate { op, tag, weight } in tag_updates.unwrap_or_default().iter() {
+    for TagUpdate { op, tag, weight } in tag_updates.iter() {
         match op {
             UpdateOperation::Set => {
-                set_tag(deps.storage, contract_id, tag.to_owned(), weight.to_owned())?;
+                set_tag(store, contract_id, tag.to_owned(), weight.to_owned())?;
             },
             UpdateOperation::Remove => {
                 let tag_bytes = IndexValue::String(tag.to_owned()).to_bytes();
-                remove_tag(deps.storage, contract_id, &tag_bytes)?;
+                remove_tag(store, contract_id, &tag_bytes)?;
             },
         }
     }
@@ -113,23 +144,89 @@ pub fn exec_update(
         name,
         value,
         address,
-    } in relation_updates.unwrap_or_default().iter()
+    } in relation_updates.iter()
     {
         match op {
             UpdateOperation::Set => {
-                set_relation(deps.storage, contract_id, name, address, value.to_owned())?;
+                set_relation(store, contract_id, name, address, value.to_owned())?;
             },
             UpdateOperation::Remove => {
                 let name_bytes = IndexValue::String(name.to_owned()).to_bytes();
-                remove_relation(deps.storage, contract_id, &name_bytes, address.as_bytes())?;
+                remove_relation(store, contract_id, &name_bytes, address.as_bytes())?;
             },
         }
     }
 
-    Ok(Response::new().add_attributes(vec![attr("action", "update")]))
+    Ok(())
+}
+
+/// Only registered indices may be written to, and only with values of the
+/// kind declared for the index. Tags, relations and index values must all fit
+/// in an index key.
+pub fn validate_updates(
+    store: &dyn Storage,
+    is_manager: bool,
+    index_updates: &[IndexUpdate],
+    tag_updates: &[TagUpdate],
+    relation_updates: &[RelationUpdate],
+) -> Result<(), ContractError> {
+    for TagUpdate { tag, .. } in tag_updates.iter() {
+        ensure_fits_in_key(&IndexValue::String(tag.to_owned()))?;
+    }
+
+    for RelationUpdate { name, value, .. } in relation_updates.iter() {
+        ensure_fits_in_key(&IndexValue::String(name.to_owned()))?;
+        if let Some(value) = value {
+            ensure_fits_in_key(value)?;
+        }
+    }
+
+    for IndexUpdate { name, value } in index_updates.iter() {
+        ensure_fits_in_key(value)?;
+
+        let index = CONFIG_CUSTOM_INDICES
+            .may_load(store, name)?
+            .ok_or_else(|| ContractError::ValidationError {
+                reason: format!("custom index not registered: {}", name),
+            })?;
+
+        if !(is_manager || index.writable_by_contracts) {
+            return Err(ContractError::NotAuthorized {
+                reason: format!("only manager can update custom index: {}", name),
+            });
+        }
+
+        if value.kind() != index.kind {
+            return Err(ContractError::ValidationError {
+                reason: format!(
+                    "custom index '{}' expects {:?} values, got {:?}",
+                    name,
+                    index.kind,
+                    value.kind()
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn ensure_fits_in_key(value: &IndexValue) -> Result<(), ContractError> {
+    if !value.fits_in_key() {
+        return Err(ContractError::ValidationError {
+            reason: format!(
+                "{:?} value exceeds {} bytes",
+                value.kind(),
+                match value.kind() {
+                    IndexValueKind::String => MAX_SIZEOF_STRING_KEY,
+                    _ => MAX_SIZEOF_BYTES_KEY,
+                }
+            ),
+        });
+    }
+    Ok(())
 }
 
-fn remove_relation(
+pub fn remove_relation(
     store: &mut dyn Storage,
     contract_id: ContractId,
     edge: &[u8],
@@ -167,13 +264,13 @@ fn set_tag(
     let tag_bytes = &IndexValue::String(tag).to_bytes();
     let weight = weight.unwrap_or_default();
     remove_tag(store, contract_id, tag_bytes)?;
-    CONTRACT_TAG_WEIGHTS.save(store, (contract_id, tag_bytes), &0)?;
+    CONTRACT_TAG_WEIGHTS.save(store, (contract_id, tag_bytes), &weight)?;
     IX_WEIGHTED_TAG.save(store, (tag_bytes, weight, contract_id), &0)?;
     IX_TAG.save(store, (tag_bytes, contract_id), &0)?;
     Ok(())
 }
 
-fn remove_tag(
+pub fn remove_tag(
     store: &mut dyn Storage,
     contract_id: ContractId,
     tag_bytes: &[u8],
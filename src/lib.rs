@@ -8,4 +8,6 @@ pub mod msg;
 #[cfg(not(feature = "library"))]
 pub mod query;
 pub mod state;
+#[cfg(all(test, not(feature = "library")))]
+mod testing;
 pub mod util;
@@ -1,16 +1,23 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Binary, Int128, Int64, Timestamp, Uint128, Uint64};
+use cosmwasm_std::{Addr, Binary, Coin, Int128, Int64, StdError, StdResult, Timestamp, Uint128, Uint64};
+use cw20::Cw20ReceiveMsg;
+use cw_utils::Expiration;
 use serde_json::{Map as SerdeMap, Value};
 
 use crate::{
     state::{
-        models::{Config, MigrationError, MigrationErrorStrategy, MigrationStatus},
+        models::{
+            CodeIdFee, CodeIdMetadata, Config, CreationLimits, CreatorPolicyScope, MigrationError,
+            MigrationErrorStrategy, MigrationResult, MigrationRollback, MigrationSessionRecord, MigrationStatus,
+            PauseState, PendingConfig, PendingManager, PresetFee, Role, ScopedCreatorPolicy,
+        },
         storage::ContractId,
     },
     util::{pad_vec, unpad_vec},
 };
 
 pub const MAX_SIZEOF_STRING_KEY: usize = 128;
+pub const MAX_SIZEOF_BYTES_KEY: usize = 128;
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -30,6 +37,14 @@ pub enum MigrationSessionMsg {
     Cancel {
         name: String,
     },
+    Rollback {
+        name: String,
+        /// Migrate message to send contracts being rolled back, defaulting to
+        /// `{}`, with overrides and templating as in `MigrationParams`
+        migrate_msg: Option<Binary>,
+        migrate_msg_overrides: Option<Vec<MigrateMsgOverride>>,
+        migrate_msg_template: Option<bool>,
+    },
 }
 
 #[cw_serde]
@@ -40,11 +55,64 @@ pub enum MigrationsExecuteMsg {
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    Configure(Config),
+    Configure(ConfigureMsg),
+    ApplyPendingConfig {},
+    /// Code ID patch operations take an optional execute-after time, like
+    /// Configure, which enqueues them as the pending config.
+    AllowCodeIds {
+        code_ids: Vec<Uint64>,
+        execute_after: Option<Timestamp>,
+    },
+    DisallowCodeIds {
+        code_ids: Vec<Uint64>,
+        execute_after: Option<Timestamp>,
+    },
+    SetDefaultCodeId {
+        code_id: Option<Uint64>,
+        execute_after: Option<Timestamp>,
+    },
+    SetCodeIdMetadata {
+        code_id: Uint64,
+        metadata: CodeIdMetadata,
+    },
+    SetChannel {
+        name: String,
+        code_id: Option<Uint64>,
+        execute_after: Option<Timestamp>,
+    },
+    CancelPendingConfig {},
     Presets(PresetsExecuteMsg),
     Create(CreateMsg),
+    CreateBatch(CreateBatchMsg),
+    Receive(Cw20ReceiveMsg),
+    Import(ImportMsg),
     Update(UpdateMsg),
+    Hide {
+        contract: Option<Addr>,
+    },
+    Unhide {
+        contract: Option<Addr>,
+    },
+    Deregister {
+        contract: Option<Addr>,
+        limit: Option<u16>,
+    },
     Migrations(MigrationsExecuteMsg),
+    Indices(IndicesExecuteMsg),
+    Quotas(QuotasExecuteMsg),
+    Creators(CreatorsExecuteMsg),
+    Roles(RolesExecuteMsg),
+    Manager(ManagerExecuteMsg),
+    Pause(PauseState),
+    Unpause {},
+}
+
+/// Messages that can be sent with CW20 tokens to pay creation fees.
+#[cw_serde]
+#[allow(clippy::large_enum_variant)]
+pub enum ReceiveMsg {
+    Create(CreateMsg),
+    CreateBatch(CreateBatchMsg),
 }
 
 #[cw_serde]
@@ -53,6 +121,56 @@ pub enum PresetsExecuteMsg {
     Remove { name: String },
 }
 
+#[cw_serde]
+pub enum IndicesExecuteMsg {
+    Set(SetIndexMsg),
+    Remove { name: String },
+}
+
+#[cw_serde]
+pub enum QuotasExecuteMsg {
+    Exempt { creator: Addr },
+    Unexempt { creator: Addr },
+}
+
+#[cw_serde]
+pub struct ConfigureMsg {
+    pub config: Config,
+    /// Delay the change until this time, after which anyone can apply it
+    pub execute_after: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub enum ManagerExecuteMsg {
+    Propose { address: Addr, expiry: Option<Expiration> },
+    Accept {},
+    Cancel {},
+}
+
+#[cw_serde]
+pub enum RolesExecuteMsg {
+    Grant { role: Role, address: Addr },
+    Revoke { role: Role, address: Addr },
+}
+
+#[cw_serde]
+pub enum CreatorsExecuteMsg {
+    SetPolicy(ScopedCreatorPolicy),
+    RemovePolicy {
+        scope: CreatorPolicyScope,
+    },
+    UpdateAllowlist {
+        scope: CreatorPolicyScope,
+        add: Option<Vec<Addr>>,
+        remove: Option<Vec<Addr>>,
+    },
+    UpdateDenylist {
+        scope: CreatorPolicyScope,
+        add: Option<Vec<Addr>>,
+        remove: Option<Vec<Addr>>,
+    },
+}
+
 #[cw_serde]
 pub enum ContractSetQueryMsg {
     InRange(ContractsInRangeQueryParams),
@@ -72,6 +190,10 @@ pub enum ContractQueryMsg {
 #[cw_serde]
 pub enum MigrationsQueryMsg {
     Session(String),
+    History { cursor: Option<u32> },
+    HistoricalSession { id: u32 },
+    SessionResults { id: u32, cursor: Option<Uint64> },
+    ContractResults { address: Addr, cursor: Option<Uint64> },
 }
 
 #[cw_serde]
@@ -80,17 +202,84 @@ pub enum PresetsQueryMsg {
     Paginate { cursor: Option<String> },
 }
 
+#[cw_serde]
+pub enum RolesQueryMsg {
+    Members { role: Role, cursor: Option<Addr> },
+    Of { address: Addr },
+}
+
+#[cw_serde]
+pub enum CreatorsQueryMsg {
+    Policies {
+        cursor: Option<CreatorPolicyScope>,
+    },
+    Allowlist {
+        scope: CreatorPolicyScope,
+        cursor: Option<Addr>,
+    },
+    Denylist {
+        scope: CreatorPolicyScope,
+        cursor: Option<Addr>,
+    },
+}
+
+#[cw_serde]
+pub enum IndicesQueryMsg {
+    Get { name: String },
+    Paginate { cursor: Option<String> },
+}
+
+#[cw_serde]
+pub struct PredictAddressQueryParams {
+    pub code_id: Option<Uint64>,
+    pub channel: Option<String>,
+    pub salt: Binary,
+}
+
 #[cw_serde]
 pub enum QueryMsg {
     Config {},
+    PredictAddress(PredictAddressQueryParams),
+    CreatorQuota {
+        creator: Addr,
+    },
     Contracts(ContractSetQueryMsg),
     Contract(ContractQueryMsg),
     Migrations(MigrationsQueryMsg),
     Presets(PresetsQueryMsg),
+    Indices(IndicesQueryMsg),
+    Creators(CreatorsQueryMsg),
+    Roles(RolesQueryMsg),
+    PendingManager {},
+    PendingConfig {},
+    AllowedCodeIds {
+        cursor: Option<Uint64>,
+    },
+    CodeId {
+        code_id: Uint64,
+    },
+    Channels {
+        cursor: Option<String>,
+    },
+    PreviewMigration {
+        params: MigrationParams,
+        cursor: Option<Binary>,
+    },
 }
 
 #[cw_serde]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// Value kinds of custom indices whose keys predate the order-preserving
+    /// encoding. Indices not listed here have their kind inferred from the
+    /// length of their stored keys, with integers assumed to be unsigned.
+    pub custom_index_kinds: Option<Vec<CustomIndexKind>>,
+}
+
+#[cw_serde]
+pub struct CustomIndexKind {
+    pub name: String,
+    pub kind: IndexValueKind,
+}
 
 #[cw_serde]
 pub enum BooleanTest {
@@ -138,7 +327,7 @@ pub struct NameValue {
 
 impl NameValue {
     pub fn as_edge_bytes(&self) -> Vec<u8> {
-        let mut bytes = self.name.as_bytes().to_vec();
+        let mut bytes = IndexValue::String(self.name.to_owned()).to_bytes();
         if let Some(value) = &self.value {
             bytes.extend(value.to_bytes());
         }
@@ -175,29 +364,164 @@ pub struct ContractTagsResponse {
     pub tags: Vec<WeightedTag>,
 }
 
+#[cw_serde]
+pub struct CustomIndexValue {
+    pub name: String,
+    pub value: IndexValue,
+}
+
 #[cw_serde]
 pub struct ContractMetadataResponse {
+    pub contract_id: ContractId,
     pub created_at: Timestamp,
     pub created_by: Addr,
     pub updated_at: Timestamp,
     pub name: Option<String>,
+    pub label: Option<String>,
     pub code_id: Uint64,
-    pub admin: Addr,
+    /// Semantic version of the code ID, if registered
+    pub code_version: Option<String>,
+    /// Code ID the contract ran before its last migration through the factory
+    pub prev_code_id: Option<Uint64>,
+    pub admin: Option<Addr>,
+    pub is_hidden: bool,
+    pub n_tags: u32,
+    pub indices: Vec<CustomIndexValue>,
 }
 
 #[cw_serde]
 pub struct MigrationSessionResponse {
+    /// ID of the session's history record
+    pub session_id: Option<u32>,
     pub errors: Vec<MigrationError>,
+    pub rollback: Option<MigrationRollback>,
+    pub rollback_errors: Vec<MigrationError>,
     pub params: MigrationParams,
     pub status: MigrationStatus,
-    pub cursor: Option<ContractId>,
+    pub cursor: Option<Binary>,
     pub retry_cursor: Option<ContractId>,
     pub n_success: u32,
     pub n_error: u32,
 }
 
 #[cw_serde]
-pub struct ConfigResponse(pub Config);
+pub struct MigrationHistoryResponse {
+    pub sessions: Vec<MigrationSessionRecord>,
+    pub cursor: Option<u32>,
+}
+
+#[cw_serde]
+pub struct MigrationResultsResponse {
+    pub results: Vec<MigrationResult>,
+    pub cursor: Option<Uint64>,
+}
+
+/// A contract that a migration session would attempt to migrate
+#[cw_serde]
+pub struct MigrationPreviewEntry {
+    pub address: Addr,
+    pub code_id: Uint64,
+    pub admin: Option<Addr>,
+    /// The migration of this contract fails unless the factory is its admin
+    pub is_factory_admin: bool,
+}
+
+#[cw_serde]
+pub struct MigrationPreviewResponse {
+    pub to_code_id: Uint64,
+    pub contracts: Vec<MigrationPreviewEntry>,
+    pub cursor: Option<Binary>,
+}
+
+/// The config, except for allowed code IDs, which are listed by the paginated
+/// AllowedCodeIds query.
+#[cw_serde]
+pub struct ConfigResponse {
+    pub managed_by: Addr,
+    pub default_code_id: Option<Uint64>,
+    pub treasury: Option<Addr>,
+    pub code_id_fees: Vec<CodeIdFee>,
+    pub preset_fees: Vec<PresetFee>,
+    pub creation_limits: Option<CreationLimits>,
+    pub config_delay: Option<u64>,
+    pub pause: Option<PauseState>,
+}
+
+#[cw_serde]
+pub struct PredictAddressResponse {
+    pub address: Addr,
+    pub code_id: Uint64,
+}
+
+#[cw_serde]
+pub struct CodeIdResponse {
+    pub code_id: Uint64,
+    pub metadata: CodeIdMetadata,
+}
+
+#[cw_serde]
+pub struct AllowedCodeIdsResponse {
+    pub code_ids: Vec<CodeIdResponse>,
+    pub default_code_id: Option<Uint64>,
+    pub cursor: Option<Uint64>,
+}
+
+#[cw_serde]
+pub struct ChannelResponse {
+    pub name: String,
+    pub code_id: Uint64,
+}
+
+#[cw_serde]
+pub struct ChannelsResponse {
+    pub channels: Vec<ChannelResponse>,
+    pub cursor: Option<String>,
+}
+
+#[cw_serde]
+pub struct PendingConfigResponse(pub Option<PendingConfig>);
+
+#[cw_serde]
+pub struct PendingManagerResponse(pub Option<PendingManager>);
+
+#[cw_serde]
+pub struct RoleMembersResponse {
+    pub role: Role,
+    pub members: Vec<Addr>,
+    pub cursor: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct AddressRolesResponse {
+    pub address: Addr,
+    pub is_manager: bool,
+    pub roles: Vec<Role>,
+}
+
+#[cw_serde]
+pub struct CreatorPolicyPaginationResponse {
+    pub policies: Vec<ScopedCreatorPolicy>,
+    pub cursor: Option<CreatorPolicyScope>,
+}
+
+#[cw_serde]
+pub struct CreatorListResponse {
+    pub creators: Vec<Addr>,
+    pub cursor: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct CreatorQuotaResponse {
+    pub creator: Addr,
+    pub is_exempt: bool,
+    pub n_created: u32,
+    pub lifetime_limit: Option<u32>,
+    pub n_created_in_window: u32,
+    pub window_limit: Option<u32>,
+    pub window_resets_at: Option<Timestamp>,
+    /// Number of contracts the creator may still create right now, if limited
+    pub n_remaining: Option<u32>,
+}
 
 #[cw_serde]
 pub struct PresetResponse {
@@ -213,6 +537,20 @@ pub struct PresetPaginationResponse {
     pub presets: Vec<PresetResponse>,
 }
 
+#[cw_serde]
+pub struct CustomIndexResponse {
+    pub name: String,
+    pub kind: IndexValueKind,
+    pub description: Option<String>,
+    pub writable_by_contracts: bool,
+}
+
+#[cw_serde]
+pub struct CustomIndexPaginationResponse {
+    pub cursor: Option<String>,
+    pub indices: Vec<CustomIndexResponse>,
+}
+
 #[cw_serde]
 pub struct ContractsByIndexResponse {
     pub addresses: Vec<Addr>,
@@ -237,11 +575,54 @@ pub struct ContractsRelatedToResponse {
 pub struct CreateMsg {
     pub preset: Option<String>,
     pub code_id: Option<Uint64>,
+    /// Release channel resolving to the code ID, instead of a code ID
+    pub channel: Option<String>,
     pub instantiate_msg: SerdeMap<String, Value>,
     pub name: Option<String>,
     pub label: String,
     pub admin: Option<Addr>,
-    pub tags: Option<Vec<String>>,
+    pub indices: Option<Vec<IndexUpdate>>,
+    pub relations: Option<Vec<RelationUpdate>>,
+    pub tags: Option<Vec<TagUpdate>>,
+    pub salt: Option<Binary>,
+    /// Funds to forward to the new contract. Defaults to all funds sent with
+    /// Create, and to none for each contract in a CreateBatch.
+    pub funds: Option<Vec<Coin>>,
+    /// Execute a FactoryCallbackMsg on the sender once the contract exists.
+    pub callback: Option<bool>,
+}
+
+/// Sent by the factory to the creator of a contract that requested a callback.
+#[cw_serde]
+pub enum FactoryCallbackMsg {
+    Created {
+        address: Addr,
+        contract_id: ContractId,
+        code_id: Uint64,
+        name: Option<String>,
+    },
+}
+
+#[cw_serde]
+pub enum BatchCreationPolicy {
+    AllOrNothing,
+    BestEffort,
+}
+
+#[cw_serde]
+pub struct CreateBatchMsg {
+    pub contracts: Vec<CreateMsg>,
+    pub policy: Option<BatchCreationPolicy>,
+}
+
+#[cw_serde]
+pub struct ImportMsg {
+    pub address: Addr,
+    pub name: Option<String>,
+    pub label: Option<String>,
+    pub indices: Option<Vec<IndexUpdate>>,
+    pub relations: Option<Vec<RelationUpdate>>,
+    pub tags: Option<Vec<TagUpdate>>,
 }
 
 #[cw_serde]
@@ -251,6 +632,14 @@ pub struct SetPresetMsg {
     pub overridable: bool,
 }
 
+#[cw_serde]
+pub struct SetIndexMsg {
+    pub name: String,
+    pub kind: IndexValueKind,
+    pub description: Option<String>,
+    pub writable_by_contracts: bool,
+}
+
 #[cw_serde]
 pub enum IndexValue {
     Bytes(Vec<u8>),
@@ -269,6 +658,25 @@ pub enum IndexValue {
     Int8(i8),
 }
 
+#[cw_serde]
+#[derive(Copy)]
+pub enum IndexValueKind {
+    Bytes,
+    String,
+    Bool,
+    Binary,
+    Uint128,
+    Uint64,
+    Uint32,
+    Uint16,
+    Uint8,
+    Int128,
+    Int64,
+    Int32,
+    Int16,
+    Int8,
+}
+
 #[cw_serde]
 pub struct IndexUpdate {
     pub name: String,
@@ -284,12 +692,25 @@ impl IndexValue {
         unpad_vec(bytes)
     }
 
+    /// Whether the value can be encoded as an index key without truncation.
+    /// Values written to storage must fit.
+    pub fn fits_in_key(&self) -> bool {
+        match self {
+            Self::Bytes(bytes) => bytes.len() <= MAX_SIZEOF_BYTES_KEY,
+            Self::Binary(x) => x.len() <= MAX_SIZEOF_BYTES_KEY,
+            Self::String(s) => s.len() <= MAX_SIZEOF_STRING_KEY,
+            _ => true,
+        }
+    }
+
+    /// Encode the value as an index key. Keys of the same kind are fixed-width
+    /// and compare byte-wise in the same order as the values themselves, so
+    /// range scans over any index come back sorted. Values that don't fit in a
+    /// key are truncated.
     pub fn to_bytes(&self) -> Vec<u8> {
         match self {
-            // TODO: Pad bytes like with string
-            Self::Bytes(bytes) => bytes.to_owned(),
-            // TODO: Pad bytes like with string
-            Self::Binary(x) => x.to_vec(),
+            Self::Bytes(bytes) => encode_bytes(bytes),
+            Self::Binary(x) => encode_bytes(x.as_slice()),
             Self::String(s) => {
                 let bytes_slice = s.as_bytes();
                 if bytes_slice.len() > MAX_SIZEOF_STRING_KEY {
@@ -301,19 +722,118 @@ impl IndexValue {
                     bytes
                 }
             },
-            Self::Uint128(x) => x.to_le_bytes().to_vec(),
-            Self::Uint64(x) => x.to_le_bytes().to_vec(),
-            Self::Uint32(x) => x.to_le_bytes().to_vec(),
-            Self::Uint16(x) => x.to_le_bytes().to_vec(),
-            Self::Uint8(x) => x.to_le_bytes().to_vec(),
-            Self::Int128(x) => x.to_le_bytes().to_vec(),
-            Self::Int64(x) => x.to_le_bytes().to_vec(),
-            Self::Int32(x) => x.to_le_bytes().to_vec(),
-            Self::Int16(x) => x.to_le_bytes().to_vec(),
-            Self::Int8(x) => x.to_le_bytes().to_vec(),
+            Self::Uint128(x) => x.u128().to_be_bytes().to_vec(),
+            Self::Uint64(x) => x.u64().to_be_bytes().to_vec(),
+            Self::Uint32(x) => x.to_be_bytes().to_vec(),
+            Self::Uint16(x) => x.to_be_bytes().to_vec(),
+            Self::Uint8(x) => x.to_be_bytes().to_vec(),
+            // Flip the sign bit so that negatives sort before positives
+            Self::Int128(x) => (x.i128() ^ i128::MIN).to_be_bytes().to_vec(),
+            Self::Int64(x) => (x.i64() ^ i64::MIN).to_be_bytes().to_vec(),
+            Self::Int32(x) => (x ^ i32::MIN).to_be_bytes().to_vec(),
+            Self::Int16(x) => (x ^ i16::MIN).to_be_bytes().to_vec(),
+            Self::Int8(x) => (x ^ i8::MIN).to_be_bytes().to_vec(),
             Self::Bool(x) => vec![if *x { 1u8 } else { 0u8 }],
         }
     }
+
+    /// Decode an index key produced by `to_bytes` back into a value.
+    pub fn from_bytes(
+        kind: IndexValueKind,
+        bytes: &[u8],
+    ) -> StdResult<Self> {
+        let invalid = || StdError::generic_err(format!("invalid {:?} index key of length {}", kind, bytes.len()));
+        Ok(match kind {
+            IndexValueKind::Bytes => Self::Bytes(decode_bytes(bytes).ok_or_else(invalid)?),
+            IndexValueKind::Binary => Self::Binary(decode_bytes(bytes).ok_or_else(invalid)?.into()),
+            IndexValueKind::String => {
+                let s = String::from_utf8(bytes.to_vec()).map_err(|_| invalid())?;
+                Self::String(s.trim_end_matches('\0').to_owned())
+            },
+            IndexValueKind::Bool => Self::Bool(u8::from_be_bytes(bytes.try_into().map_err(|_| invalid())?) != 0),
+            IndexValueKind::Uint128 => {
+                Self::Uint128(u128::from_be_bytes(bytes.try_into().map_err(|_| invalid())?).into())
+            },
+            IndexValueKind::Uint64 => Self::Uint64(u64::from_be_bytes(bytes.try_into().map_err(|_| invalid())?).into()),
+            IndexValueKind::Uint32 => Self::Uint32(u32::from_be_bytes(bytes.try_into().map_err(|_| invalid())?)),
+            IndexValueKind::Uint16 => Self::Uint16(u16::from_be_bytes(bytes.try_into().map_err(|_| invalid())?)),
+            IndexValueKind::Uint8 => Self::Uint8(u8::from_be_bytes(bytes.try_into().map_err(|_| invalid())?)),
+            IndexValueKind::Int128 => {
+                Self::Int128((i128::from_be_bytes(bytes.try_into().map_err(|_| invalid())?) ^ i128::MIN).into())
+            },
+            IndexValueKind::Int64 => {
+                Self::Int64((i64::from_be_bytes(bytes.try_into().map_err(|_| invalid())?) ^ i64::MIN).into())
+            },
+            IndexValueKind::Int32 => {
+                Self::Int32(i32::from_be_bytes(bytes.try_into().map_err(|_| invalid())?) ^ i32::MIN)
+            },
+            IndexValueKind::Int16 => {
+                Self::Int16(i16::from_be_bytes(bytes.try_into().map_err(|_| invalid())?) ^ i16::MIN)
+            },
+            IndexValueKind::Int8 => Self::Int8(i8::from_be_bytes(bytes.try_into().map_err(|_| invalid())?) ^ i8::MIN),
+        })
+    }
+
+    /// Plain JSON form of the value, with big integers as strings and bytes as
+    /// base64, the way CosmWasm types are serialized.
+    pub fn to_json_value(&self) -> Value {
+        match self {
+            Self::Bytes(x) => Value::String(Binary::from(x.as_slice()).to_base64()),
+            Self::Binary(x) => Value::String(x.to_base64()),
+            Self::String(x) => Value::String(x.to_owned()),
+            Self::Bool(x) => Value::Bool(*x),
+            Self::Uint128(x) => Value::String(x.to_string()),
+            Self::Uint64(x) => Value::String(x.to_string()),
+            Self::Uint32(x) => Value::from(*x),
+            Self::Uint16(x) => Value::from(*x),
+            Self::Uint8(x) => Value::from(*x),
+            Self::Int128(x) => Value::String(x.to_string()),
+            Self::Int64(x) => Value::String(x.to_string()),
+            Self::Int32(x) => Value::from(*x),
+            Self::Int16(x) => Value::from(*x),
+            Self::Int8(x) => Value::from(*x),
+        }
+    }
+
+    pub fn kind(&self) -> IndexValueKind {
+        match self {
+            Self::Bytes(_) => IndexValueKind::Bytes,
+            Self::String(_) => IndexValueKind::String,
+            Self::Bool(_) => IndexValueKind::Bool,
+            Self::Binary(_) => IndexValueKind::Binary,
+            Self::Uint128(_) => IndexValueKind::Uint128,
+            Self::Uint64(_) => IndexValueKind::Uint64,
+            Self::Uint32(_) => IndexValueKind::Uint32,
+            Self::Uint16(_) => IndexValueKind::Uint16,
+            Self::Uint8(_) => IndexValueKind::Uint8,
+            Self::Int128(_) => IndexValueKind::Int128,
+            Self::Int64(_) => IndexValueKind::Int64,
+            Self::Int32(_) => IndexValueKind::Int32,
+            Self::Int16(_) => IndexValueKind::Int16,
+            Self::Int8(_) => IndexValueKind::Int8,
+        }
+    }
+}
+
+/// Zero-pad raw bytes to a fixed width and append the original length, so
+/// that shorter values sort before longer values sharing the same prefix,
+/// even when the longer value only adds trailing zeros.
+fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    let n = bytes.len().min(MAX_SIZEOF_BYTES_KEY);
+    let mut encoded = pad_vec(bytes[..n].to_vec(), MAX_SIZEOF_BYTES_KEY);
+    encoded.push(n as u8);
+    encoded
+}
+
+fn decode_bytes(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.len() != MAX_SIZEOF_BYTES_KEY + 1 {
+        return None;
+    }
+    let n = bytes[MAX_SIZEOF_BYTES_KEY] as usize;
+    if n > MAX_SIZEOF_BYTES_KEY {
+        return None;
+    }
+    Some(bytes[..n].to_vec())
 }
 
 #[cw_serde]
@@ -415,6 +935,7 @@ pub struct ContractsInRangeQueryParams {
     pub stop: Option<IndexRangeBound>,
     pub limit: Option<u16>,
     pub desc: Option<bool>,
+    pub include_hidden: Option<bool>,
 }
 
 #[cw_serde]
@@ -425,6 +946,7 @@ pub struct ContractsByTagQueryParams {
     pub max_weight: Option<TagWeightRangeBound>,
     pub limit: Option<u16>,
     pub desc: Option<bool>,
+    pub include_hidden: Option<bool>,
 }
 
 #[cw_serde]
@@ -435,6 +957,7 @@ pub struct ContractsRelatedToParams {
     pub address: Addr,
     pub start: Option<RangeQueryBound<NameValue>>,
     pub stop: Option<RangeQueryBound<NameValue>>,
+    pub include_hidden: Option<bool>,
 }
 
 #[cw_serde]
@@ -443,14 +966,173 @@ pub struct MigrationParams {
     pub batch_size: Option<u16>,
     pub error_strategy: MigrationErrorStrategy,
     pub migrate_msg: Option<Binary>,
+    /// Migrate messages to send specific contracts instead of `migrate_msg`
+    pub migrate_msg_overrides: Option<Vec<MigrateMsgOverride>>,
+    /// Treat migrate messages as JSON templates, filling in placeholders like
+    /// `{{contract_address}}`, `{{contract_id}}`, `{{name}}` and `{{index:foo}}`
+    /// for each contract
+    pub migrate_msg_template: Option<bool>,
     pub from_code_id: Option<Uint64>,
-    pub to_code_id: Uint64,
+    /// Code ID to migrate to, resolved from `to_channel` if not given
+    pub to_code_id: Option<Uint64>,
+    pub to_channel: Option<String>,
+    /// Contracts to migrate, defaulting to every contract in the factory
+    pub target: Option<MigrationTarget>,
+}
+
+#[cw_serde]
+pub struct MigrateMsgOverride {
+    pub contract: Addr,
+    pub migrate_msg: Binary,
+}
+
+/// Selects the subset of contracts a migration session walks through.
+#[cw_serde]
+pub enum MigrationTarget {
+    Tag {
+        tag: String,
+        min_weight: Option<TagWeightRangeBound>,
+        max_weight: Option<TagWeightRangeBound>,
+    },
+    IndexRange {
+        index: String,
+        start: Option<IndexRangeBound>,
+        stop: Option<IndexRangeBound>,
+    },
+    RelatedTo {
+        address: Addr,
+        name: Option<String>,
+    },
+    CreatedBy(Addr),
+    Addresses(Vec<Addr>),
 }
 
 #[cw_serde]
 pub struct SingletonMigrationParams {
-    pub to_code_id: Uint64,
+    pub to_code_id: Option<Uint64>,
+    pub to_channel: Option<String>,
     pub from_code_id: Option<Uint64>,
     pub migrate_msg: Option<Binary>,
     pub contract: Addr,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn assert_order_preserved<T: Ord>(
+        a: T,
+        b: T,
+        encode: fn(T) -> IndexValue,
+    ) {
+        let ord = a.cmp(&b);
+        let (a_bytes, b_bytes) = (encode(a).to_bytes(), encode(b).to_bytes());
+        assert_eq!(a_bytes.len(), b_bytes.len());
+        assert_eq!(ord, a_bytes.cmp(&b_bytes));
+    }
+
+    proptest! {
+        #[test]
+        fn uint128_order(a: u128, b: u128) {
+            assert_order_preserved(a, b, |x| IndexValue::Uint128(x.into()));
+        }
+
+        #[test]
+        fn uint64_order(a: u64, b: u64) {
+            assert_order_preserved(a, b, |x| IndexValue::Uint64(x.into()));
+        }
+
+        #[test]
+        fn uint32_order(a: u32, b: u32) {
+            assert_order_preserved(a, b, IndexValue::Uint32);
+        }
+
+        #[test]
+        fn uint16_order(a: u16, b: u16) {
+            assert_order_preserved(a, b, IndexValue::Uint16);
+        }
+
+        #[test]
+        fn uint8_order(a: u8, b: u8) {
+            assert_order_preserved(a, b, IndexValue::Uint8);
+        }
+
+        #[test]
+        fn int128_order(a: i128, b: i128) {
+            assert_order_preserved(a, b, |x| IndexValue::Int128(x.into()));
+        }
+
+        #[test]
+        fn int64_order(a: i64, b: i64) {
+            assert_order_preserved(a, b, |x| IndexValue::Int64(x.into()));
+        }
+
+        #[test]
+        fn int32_order(a: i32, b: i32) {
+            assert_order_preserved(a, b, IndexValue::Int32);
+        }
+
+        #[test]
+        fn int16_order(a: i16, b: i16) {
+            assert_order_preserved(a, b, IndexValue::Int16);
+        }
+
+        #[test]
+        fn int8_order(a: i8, b: i8) {
+            assert_order_preserved(a, b, IndexValue::Int8);
+        }
+
+        #[test]
+        fn bool_order(a: bool, b: bool) {
+            assert_order_preserved(a, b, IndexValue::Bool);
+        }
+
+        #[test]
+        fn string_order(a in "[^\u{0}]{0,32}", b in "[^\u{0}]{0,32}") {
+            assert_order_preserved(a, b, IndexValue::String);
+        }
+
+        #[test]
+        fn bytes_order(
+            a in prop::collection::vec(0u8..4, 0..=MAX_SIZEOF_BYTES_KEY),
+            b in prop::collection::vec(0u8..4, 0..=MAX_SIZEOF_BYTES_KEY),
+        ) {
+            assert_order_preserved(a.clone(), b.clone(), IndexValue::Bytes);
+            assert_order_preserved(a, b, |x| IndexValue::Binary(x.into()));
+        }
+
+        #[test]
+        fn roundtrip(x: i64, y: u128, bytes in prop::collection::vec(any::<u8>(), 0..=MAX_SIZEOF_BYTES_KEY)) {
+            for value in [IndexValue::Int64(x.into()), IndexValue::Uint128(y.into()), IndexValue::Bytes(bytes.clone())] {
+                prop_assert_eq!(IndexValue::from_bytes(value.kind(), &value.to_bytes())?, value);
+            }
+        }
+
+        #[test]
+        fn oversized_values_dont_fit_in_key(
+            bytes in prop::collection::vec(any::<u8>(), MAX_SIZEOF_BYTES_KEY + 1..=2 * MAX_SIZEOF_BYTES_KEY),
+            s in "[a-z]{129,256}",
+        ) {
+            prop_assert!(!IndexValue::Bytes(bytes.clone()).fits_in_key());
+            prop_assert!(!IndexValue::Binary(bytes.into()).fits_in_key());
+            prop_assert!(!IndexValue::String(s).fits_in_key());
+        }
+
+        #[test]
+        fn bounded_values_fit_in_key(
+            bytes in prop::collection::vec(any::<u8>(), 0..=MAX_SIZEOF_BYTES_KEY),
+            s in "[a-z]{0,128}",
+        ) {
+            prop_assert!(IndexValue::Bytes(bytes.clone()).fits_in_key());
+            prop_assert!(IndexValue::Binary(bytes.into()).fits_in_key());
+            prop_assert!(IndexValue::String(s).fits_in_key());
+        }
+    }
+
+    #[test]
+    fn bytes_with_trailing_zeros_sort_after_prefix() {
+        assert_order_preserved(vec![1], vec![1, 0], IndexValue::Bytes);
+        assert_order_preserved(vec![], vec![0], IndexValue::Bytes);
+    }
+}
@@ -0,0 +1,84 @@
+use std::marker::PhantomData;
+
+use cosmwasm_std::{Order, Uint64};
+use cw_storage_plus::Bound;
+
+use crate::{
+    error::ContractError,
+    msg::{AllowedCodeIdsResponse, ChannelResponse, ChannelsResponse, CodeIdResponse},
+    query::ReadonlyContext,
+    state::storage::{CONFIG_ALLOWED_CODE_IDS, CONFIG_CHANNELS, CONFIG_DEFAULT_CODE_ID},
+};
+
+const PAGE_SIZE: usize = 50;
+
+pub fn query_allowed_code_ids(
+    ctx: ReadonlyContext,
+    cursor: Option<Uint64>,
+) -> Result<AllowedCodeIdsResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+
+    let min_bound = cursor.map(|x| Bound::exclusive(x.u64()));
+
+    let code_ids = CONFIG_ALLOWED_CODE_IDS
+        .range(deps.storage, min_bound, None, Order::Ascending)
+        .take(PAGE_SIZE)
+        .map(|r| {
+            r.map(|(code_id, metadata)| CodeIdResponse {
+                code_id: code_id.into(),
+                metadata,
+            })
+        })
+        .collect::<Result<Vec<CodeIdResponse>, _>>()?;
+
+    Ok(AllowedCodeIdsResponse {
+        cursor: if code_ids.len() == PAGE_SIZE {
+            code_ids.last().map(|x| x.code_id)
+        } else {
+            None
+        },
+        default_code_id: CONFIG_DEFAULT_CODE_ID.may_load(deps.storage)?,
+        code_ids,
+    })
+}
+
+pub fn query_code_id(
+    ctx: ReadonlyContext,
+    code_id: Uint64,
+) -> Result<CodeIdResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+    Ok(CodeIdResponse {
+        metadata: CONFIG_ALLOWED_CODE_IDS.load(deps.storage, code_id.u64())?,
+        code_id,
+    })
+}
+
+pub fn query_channels(
+    ctx: ReadonlyContext,
+    cursor: Option<String>,
+) -> Result<ChannelsResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+
+    let mut boxed_name: Box<String> = Box::default();
+    let min_bound = if let Some(s) = cursor {
+        *boxed_name = s;
+        Some(Bound::Exclusive((boxed_name.as_ref(), PhantomData)))
+    } else {
+        None
+    };
+
+    let channels = CONFIG_CHANNELS
+        .range(deps.storage, min_bound, None, Order::Ascending)
+        .take(PAGE_SIZE)
+        .map(|r| r.map(|(name, code_id)| ChannelResponse { name, code_id }))
+        .collect::<Result<Vec<ChannelResponse>, _>>()?;
+
+    Ok(ChannelsResponse {
+        cursor: if channels.len() == PAGE_SIZE {
+            channels.last().map(|x| x.name.to_owned())
+        } else {
+            None
+        },
+        channels,
+    })
+}
@@ -1,11 +1,14 @@
-use cosmwasm_std::Order;
+use cosmwasm_std::{Order, StdResult};
 
 use crate::{
     error::ContractError,
     msg::ConfigResponse,
     state::{
-        models::Config,
-        storage::{CONFIG_ALLOWED_CODE_IDS, CONFIG_DEFAULT_CODE_ID, MANAGED_BY},
+        models::{CodeIdFee, PresetFee},
+        storage::{
+            CONFIG_CODE_ID_FEES, CONFIG_CREATION_LIMITS, CONFIG_DEFAULT_CODE_ID, CONFIG_DELAY, CONFIG_PRESET_FEES,
+            CONFIG_TREASURY, MANAGED_BY, PAUSE_STATE,
+        },
     },
 };
 
@@ -13,12 +16,25 @@ use super::ReadonlyContext;
 
 pub fn query_config(ctx: ReadonlyContext) -> Result<ConfigResponse, ContractError> {
     let ReadonlyContext { deps, .. } = ctx;
-    Ok(ConfigResponse(Config {
+    Ok(ConfigResponse {
         managed_by: MANAGED_BY.load(deps.storage)?,
         default_code_id: CONFIG_DEFAULT_CODE_ID.may_load(deps.storage)?,
-        allowed_code_ids: CONFIG_ALLOWED_CODE_IDS
-            .keys(deps.storage, None, None, Order::Ascending)
-            .map(|r| r.unwrap().into())
-            .collect(),
-    }))
+        treasury: CONFIG_TREASURY.may_load(deps.storage)?,
+        code_id_fees: CONFIG_CODE_ID_FEES
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|r| {
+                r.map(|(code_id, fee)| CodeIdFee {
+                    code_id: code_id.into(),
+                    fee,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?,
+        preset_fees: CONFIG_PRESET_FEES
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|r| r.map(|(preset, fee)| PresetFee { preset, fee }))
+            .collect::<StdResult<Vec<_>>>()?,
+        creation_limits: CONFIG_CREATION_LIMITS.may_load(deps.storage)?,
+        config_delay: CONFIG_DELAY.may_load(deps.storage)?,
+        pause: PAUSE_STATE.may_load(deps.storage)?,
+    })
 }
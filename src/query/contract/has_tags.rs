@@ -20,7 +20,7 @@ pub fn query_contract_has_tags(
                     return Ok(false);
                 }
             }
-            return Ok(true);
+            Ok(true)
         },
         BooleanTest::Or => {
             for selector in msg.tags.iter() {
@@ -28,7 +28,7 @@ pub fn query_contract_has_tags(
                     return Ok(false);
                 }
             }
-            return Ok(true);
+            Ok(true)
         },
         BooleanTest::Xor => {
             let mut test_passes = false;
@@ -41,7 +41,7 @@ pub fn query_contract_has_tags(
                     }
                 }
             }
-            return Ok(test_passes);
+            Ok(test_passes)
         },
     }
 }
@@ -21,7 +21,7 @@ pub fn query_contract_is_related_to(
                     return Ok(false);
                 }
             }
-            return Ok(true);
+            Ok(true)
         },
         BooleanTest::Or => {
             for x in msg.relations.iter() {
@@ -29,7 +29,7 @@ pub fn query_contract_is_related_to(
                     return Ok(false);
                 }
             }
-            return Ok(true);
+            Ok(true)
         },
         BooleanTest::Xor => {
             let mut test_passes = false;
@@ -42,7 +42,7 @@ pub fn query_contract_is_related_to(
                     }
                 }
             }
-            return Ok(test_passes);
+            Ok(test_passes)
         },
     }
 }
@@ -1,14 +1,16 @@
-use cosmwasm_std::{Addr, Timestamp};
-use cw_storage_plus::KeyDeserialize;
+use cosmwasm_std::{Addr, Order, StdError, StdResult, Storage, Timestamp};
+use cw_storage_plus::Map;
 
 use crate::{
     error::ContractError,
-    msg::ContractMetadataResponse,
+    msg::{ContractMetadataResponse, CustomIndexValue, IndexValue, IndexValueKind},
     query::ReadonlyContext,
     state::storage::{
-        CONTRACT_ADDR_2_ID, CONTRACT_ID_2_NAME, ID_2_ADMIN, ID_2_CODE_ID, ID_2_CREATED_AT, ID_2_CREATED_BY,
-        ID_2_UPDATED_AT,
+        ContractId, CONFIG_ALLOWED_CODE_IDS, CONFIG_CUSTOM_INDICES, CONTRACT_ADDR_2_ID, CONTRACT_CUSTOM_IX_VALUES,
+        CONTRACT_ID_2_LABEL, CONTRACT_ID_2_NAME, CONTRACT_ID_2_PREV_CODE_ID, CONTRACT_TAG_WEIGHTS, ID_2_ADMIN,
+        ID_2_CODE_ID, ID_2_CREATED_AT, ID_2_CREATED_BY, ID_2_UPDATED_AT,
     },
+    util::is_hidden,
 };
 
 pub fn query_contract_metadata(
@@ -19,33 +21,71 @@ pub fn query_contract_metadata(
     let addr = deps.api.addr_validate(contract.as_str())?;
     let id = CONTRACT_ADDR_2_ID.load(deps.storage, &addr)?;
 
-    let created_at_nanos: u64 = u64::from_le_bytes(
-        ID_2_CREATED_AT
-            .load(deps.storage, id)?
-            .try_into()
-            .expect("incorrect created_at byte length"),
-    );
-
-    let updated_at_nanos: u64 = u64::from_le_bytes(
-        ID_2_UPDATED_AT
-            .load(deps.storage, id)?
-            .try_into()
-            .expect("incorrect upated_at byte length"),
-    );
-
-    let code_id = u64::from_le_bytes(
-        ID_2_CODE_ID
-            .load(deps.storage, id)?
-            .try_into()
-            .expect("incorrect upated_at byte length"),
-    );
+    // Resolve the value of each custom index the contract has been given
+    let mut indices: Vec<CustomIndexValue> = Vec::with_capacity(4);
+    for result in CONTRACT_CUSTOM_IX_VALUES
+        .prefix(id)
+        .range(deps.storage, None, None, Order::Ascending)
+    {
+        let (name, bytes) = result?;
+        if let Some(index) = CONFIG_CUSTOM_INDICES.may_load(deps.storage, &name)? {
+            indices.push(CustomIndexValue {
+                value: IndexValue::from_bytes(index.kind, &bytes)?,
+                name,
+            });
+        }
+    }
+
+    // Version of the contract's code, according to the code ID registry
+    let code_id = load_u64(deps.storage, ID_2_CODE_ID, id)?;
+    let code_version = CONFIG_ALLOWED_CODE_IDS
+        .may_load(deps.storage, code_id)?
+        .and_then(|x| x.version);
+
+    let n_tags = CONTRACT_TAG_WEIGHTS
+        .prefix(id)
+        .keys_raw(deps.storage, None, None, Order::Ascending)
+        .count() as u32;
 
     Ok(ContractMetadataResponse {
-        created_at: Timestamp::from_nanos(created_at_nanos),
-        created_by: Addr::from_slice(ID_2_CREATED_BY.load(deps.storage, id)?.as_slice())?,
-        updated_at: Timestamp::from_nanos(updated_at_nanos),
+        contract_id: id,
+        created_at: Timestamp::from_nanos(load_u64(deps.storage, ID_2_CREATED_AT, id)?),
+        created_by: load_addr(deps.storage, ID_2_CREATED_BY, id)?,
+        updated_at: Timestamp::from_nanos(load_u64(deps.storage, ID_2_UPDATED_AT, id)?),
         name: CONTRACT_ID_2_NAME.may_load(deps.storage, id)?,
-        admin: Addr::from_slice(ID_2_ADMIN.load(deps.storage, id)?.as_slice())?,
+        label: CONTRACT_ID_2_LABEL.may_load(deps.storage, id)?,
+        admin: if ID_2_ADMIN.has(deps.storage, id) {
+            Some(load_addr(deps.storage, ID_2_ADMIN, id)?)
+        } else {
+            None
+        },
         code_id: code_id.into(),
+        code_version,
+        prev_code_id: CONTRACT_ID_2_PREV_CODE_ID.may_load(deps.storage, id)?,
+        is_hidden: is_hidden(deps.storage, id)?,
+        n_tags,
+        indices,
     })
 }
+
+fn load_u64(
+    store: &dyn Storage,
+    map: Map<ContractId, Vec<u8>>,
+    id: ContractId,
+) -> StdResult<u64> {
+    match IndexValue::from_bytes(IndexValueKind::Uint64, &map.load(store, id)?)? {
+        IndexValue::Uint64(n) => Ok(n.u64()),
+        _ => Err(StdError::generic_err("expected Uint64 index value")),
+    }
+}
+
+fn load_addr(
+    store: &dyn Storage,
+    map: Map<ContractId, Vec<u8>>,
+    id: ContractId,
+) -> StdResult<Addr> {
+    match IndexValue::from_bytes(IndexValueKind::String, &map.load(store, id)?)? {
+        IndexValue::String(s) => Ok(Addr::unchecked(s)),
+        _ => Err(StdError::generic_err("expected String index value")),
+    }
+}
@@ -30,8 +30,8 @@ pub fn query_contract_relations(
     let (limit, desc) = prepare_limit_and_desc(limit, desc);
 
     // Set the starting point for iterating over related contracts
-    let mut from_bytes_box: Box<Vec<u8>> = Box::new(vec![]);
-    let mut from_edge_box: Box<Vec<u8>> = Box::new(vec![]);
+    let mut from_bytes_box: Box<Vec<u8>> = Box::default();
+    let mut from_edge_box: Box<Vec<u8>> = Box::default();
 
     let from_bound = match cursor {
         // Continue iterating after cursor
@@ -70,8 +70,8 @@ pub fn query_contract_relations(
     };
 
     // Set the bound where iteration should stop
-    let mut to_bytes_box: Box<Vec<u8>> = Box::new(vec![]);
-    let mut to_edge_box: Box<Vec<u8>> = Box::new(vec![]);
+    let mut to_bytes_box: Box<Vec<u8>> = Box::default();
+    let mut to_edge_box: Box<Vec<u8>> = Box::default();
 
     let to_bound = if let Some(stop) = stop {
         *to_bytes_box = IndexValue::String("".to_owned()).to_bytes();
@@ -118,9 +118,7 @@ pub fn query_contract_relations(
     }
 
     Ok(ContractRelationsResponse {
-        cursor: related_addrs
-            .last()
-            .and_then(|x| Some((x.name.to_owned(), x.address.to_owned()))),
+        cursor: related_addrs.last().map(|x| (x.name.to_owned(), x.address.to_owned())),
         relations: related_addrs,
     })
 }
@@ -30,7 +30,7 @@ pub fn query_contract_tags(
     let (limit, desc) = prepare_limit_and_desc(limit, desc);
 
     // Set the starting point for iterating over tags
-    let mut from_bytes_box: Box<Vec<u8>> = Box::new(vec![]);
+    let mut from_bytes_box: Box<Vec<u8>> = Box::default();
     let from_bound = match cursor {
         // Continue iterating starting from the map item after cursor tag.
         Some(cursor_tag) => {
@@ -60,7 +60,7 @@ pub fn query_contract_tags(
     };
 
     // Set the bound where iteration should stop
-    let mut to_bytes_box: Box<Vec<u8>> = Box::new(vec![]);
+    let mut to_bytes_box: Box<Vec<u8>> = Box::default();
     let to_bound = if let Some(stop) = stop {
         match stop {
             RangeQueryBound::Exclusive(tag) => {
@@ -101,7 +101,7 @@ pub fn query_contract_tags(
     }
 
     Ok(ContractTagsResponse {
-        cursor: weighted_tags.last().and_then(|t| Some(t.tag.to_owned())),
+        cursor: weighted_tags.last().map(|t| t.tag.to_owned()),
         tags: weighted_tags,
     })
 }
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use cosmwasm_std::{Addr, Order, Storage};
+use cosmwasm_std::{Addr, Order, StdResult, Storage};
 use cw_storage_plus::{Bound, Map};
 
 use crate::{
@@ -10,15 +10,18 @@ use crate::{
     state::{
         build_index_storage_key,
         storage::{
-            ContractId, IndexMap, CONTRACT_ID_2_ADDR, IX_ADMIN, IX_CODE_ID, IX_CREATED_AT, IX_CREATED_BY, IX_TAG,
-            IX_UPDATED_AT,
+            ContractId, IndexMap, CONFIG_CUSTOM_INDICES, CONTRACT_ID_2_ADDR, IX_ADMIN, IX_CODE_ID, IX_CREATED_AT,
+            IX_CREATED_BY, IX_TAG, IX_UPDATED_AT,
         },
     },
+    util::is_hidden,
 };
 
 const DEFAULT_LIMIT: usize = 100;
 const MAX_LIMIT: usize = 500;
 
+type IndexCursor = (Vec<u8>, ContractId);
+
 pub fn query_contracts_in_range(
     ctx: ReadonlyContext,
     params: ContractsInRangeQueryParams,
@@ -28,9 +31,31 @@ pub fn query_contracts_in_range(
     // Normalize limit within acceptable range
     let limit = params
         .limit
-        .and_then(|x| Some((x as usize).clamp(1, MAX_LIMIT)))
+        .map(|x| (x as usize).clamp(1, MAX_LIMIT))
         .unwrap_or(DEFAULT_LIMIT);
 
+    // Ensure range bounds match the declared value kind of custom indices
+    if let IndexSelector::Custom(index_name) = &params.index {
+        let index = CONFIG_CUSTOM_INDICES
+            .may_load(deps.storage, index_name)?
+            .ok_or_else(|| ContractError::ValidationError {
+                reason: format!("custom index not registered: {}", index_name),
+            })?;
+        for bound in [&params.start, &params.stop].into_iter().flatten() {
+            let (IndexRangeBound::Exclusive(value) | IndexRangeBound::Inclusive(value)) = bound;
+            if value.kind() != index.kind {
+                return Err(ContractError::ValidationError {
+                    reason: format!(
+                        "custom index '{}' expects {:?} bounds, got {:?}",
+                        index_name,
+                        index.kind,
+                        value.kind()
+                    ),
+                });
+            }
+        }
+    }
+
     // Get a vec of queried contract ID's
     let (contract_ids, cursor) = scan_index(deps.storage, &params, limit)?;
 
@@ -50,11 +75,11 @@ fn scan_index(
     store: &dyn Storage,
     params: &ContractsInRangeQueryParams,
     limit: usize,
-) -> Result<(Vec<ContractId>, Option<(Vec<u8>, ContractId)>), ContractError> {
+) -> Result<(Vec<ContractId>, Option<IndexCursor>), ContractError> {
     let desc = params.desc.unwrap_or_default();
-    let mut custom_index_storage_key: Box<String> = Box::new(String::new());
-    let mut start_bytes: Box<Vec<u8>> = Box::new(vec![]);
-    let mut stop_bytes: Box<Vec<u8>> = Box::new(vec![]);
+    let mut custom_index_storage_key: Box<String> = Box::default();
+    let mut start_bytes: Box<Vec<u8>> = Box::default();
+    let mut stop_bytes: Box<Vec<u8>> = Box::default();
 
     // Get the index map to scan
     let map = match &params.index {
@@ -65,7 +90,7 @@ fn scan_index(
         IndexSelector::Admin => IX_ADMIN,
         IndexSelector::Tag => IX_TAG,
         IndexSelector::Custom(index_name) => {
-            *custom_index_storage_key = build_index_storage_key(&index_name);
+            *custom_index_storage_key = build_index_storage_key(index_name);
             let map: IndexMap = Map::new(custom_index_storage_key.as_str());
             map
         },
@@ -74,7 +99,7 @@ fn scan_index(
     let from_bound = match &params.cursor {
         Some((bytes, id)) => {
             *start_bytes = match &params.index {
-                IndexSelector::Custom(_) | IndexSelector::Tag => IndexValue::pad(bytes.to_owned()),
+                IndexSelector::Tag => IndexValue::pad(bytes.to_owned()),
                 _ => bytes.to_owned(),
             };
             Some(Bound::Exclusive(((start_bytes.as_slice(), *id), PhantomData)))
@@ -120,20 +145,25 @@ fn scan_index(
         (from_bound, to_bound, Order::Ascending)
     };
 
+    // Skip over hidden contracts unless explicitly requested
+    let include_hidden = params.include_hidden.unwrap_or_default();
     let keys: Vec<_> = map
         .keys(store, min_bound, max_bound, order)
+        .filter_map(|r| {
+            r.and_then(|k| Ok((include_hidden || !is_hidden(store, k.1)?).then_some(k)))
+                .transpose()
+        })
         .take(limit)
-        .map(|r| r.unwrap())
-        .collect();
+        .collect::<StdResult<_>>()?;
 
     let contract_ids: Vec<ContractId> = keys.iter().map(|k| k.1).collect();
     let cursor = if keys.len() == limit {
-        keys.last().and_then(|(a, b)| {
+        keys.last().map(|(a, b)| {
             let bytes = match &params.index {
-                IndexSelector::Custom(_) | IndexSelector::Tag => IndexValue::strip(a.to_vec()),
+                IndexSelector::Tag => IndexValue::strip(a.to_vec()),
                 _ => a.to_vec(),
             };
-            Some((bytes, *b))
+            (bytes, *b)
         })
     } else {
         None
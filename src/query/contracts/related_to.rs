@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use cosmwasm_std::{Addr, Order, Storage};
+use cosmwasm_std::{Addr, Order, StdResult, Storage};
 use cw_storage_plus::Bound;
 
 use crate::{
@@ -8,11 +8,14 @@ use crate::{
     msg::{ContractsRelatedToParams, ContractsRelatedToResponse, IndexValue, RangeQueryBound},
     query::ReadonlyContext,
     state::storage::{ContractId, CONTRACT_ID_2_ADDR, IX_REL_ADDR, IX_REL_CONTRACT_ADDR},
+    util::is_hidden,
 };
 
 const DEFAULT_LIMIT: usize = 100;
 const MAX_LIMIT: usize = 500;
 
+type RelatedContract = (ContractId, Option<IndexValue>);
+
 pub fn query_contracts_related_to(
     ctx: ReadonlyContext,
     params: ContractsRelatedToParams,
@@ -22,7 +25,7 @@ pub fn query_contracts_related_to(
     // Normalize limit within acceptable range
     let limit = params
         .limit
-        .and_then(|x| Some((x as usize).clamp(1, MAX_LIMIT)))
+        .map(|x| (x as usize).clamp(1, MAX_LIMIT))
         .unwrap_or(DEFAULT_LIMIT);
 
     // Get a vec of queried contract ID's
@@ -47,7 +50,7 @@ fn scan_relations(
     store: &dyn Storage,
     params: &ContractsRelatedToParams,
     limit: usize,
-) -> Result<(Vec<(ContractId, Option<IndexValue>)>, Option<ContractId>), ContractError> {
+) -> Result<(Vec<RelatedContract>, Option<ContractId>), ContractError> {
     // Convert tag to u8 slice
     // let name_bytes_vec = IndexValue::String(params.name.clone()).to_bytes();
     // let name_bytes = name_bytes_vec.as_slice();
@@ -55,7 +58,7 @@ fn scan_relations(
     let desc = params.desc.unwrap_or_default();
 
     // Build the bound from which we're resuming iteration
-    let mut from_edge_box: Box<Vec<u8>> = Box::new(vec![]);
+    let mut from_edge_box: Box<Vec<u8>> = Box::default();
     let from_bound = match &params.cursor {
         Some((id, edge)) => {
             *from_edge_box = edge.clone();
@@ -89,7 +92,7 @@ fn scan_relations(
     };
 
     // Build the terminal bound
-    let mut to_edge_box: Box<Vec<u8>> = Box::new(vec![]);
+    let mut to_edge_box: Box<Vec<u8>> = Box::default();
     let to_bound = {
         let id = if desc { ContractId::MIN } else { ContractId::MAX };
         match &params.stop {
@@ -122,21 +125,25 @@ fn scan_relations(
 
     // Load contract IDs and weights, ordered by weight
     // NOTE: Could be improved by just using a map.prefix()...
+    let include_hidden = params.include_hidden.unwrap_or_default();
     let keys: Vec<_> = IX_REL_ADDR
         .keys(store, min_bound, max_bound, order)
-        .map(|r| r.unwrap())
+        .filter_map(|r| {
+            r.and_then(|k| Ok((include_hidden || !is_hidden(store, k.2)?).then_some(k)))
+                .transpose()
+        })
         .take(limit)
-        .collect();
+        .collect::<StdResult<_>>()?;
 
     let mut contract_ids_and_values: Vec<(ContractId, Option<IndexValue>)> = Vec::with_capacity(keys.len());
 
     for (addr, edge, contract_id) in keys.iter() {
-        let value = IX_REL_CONTRACT_ADDR.load(store, (*contract_id, edge, &addr))?;
+        let value = IX_REL_CONTRACT_ADDR.load(store, (*contract_id, edge, addr))?;
         contract_ids_and_values.push((*contract_id, value));
     }
 
     let cursor = if contract_ids_and_values.len() == limit {
-        contract_ids_and_values.last().and_then(|(id, _)| Some(*id))
+        contract_ids_and_values.last().map(|(id, _)| *id)
     } else {
         None
     };
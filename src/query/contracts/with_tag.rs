@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use cosmwasm_std::{Addr, Order, Storage};
+use cosmwasm_std::{Addr, Order, StdResult, Storage};
 use cw_storage_plus::Bound;
 
 use crate::{
@@ -8,11 +8,15 @@ use crate::{
     msg::{ContractsByTagQueryParams, ContractsByTagResponse, IndexValue, TagWeightRangeBound},
     query::ReadonlyContext,
     state::storage::{ContractId, CONTRACT_ID_2_ADDR, IX_WEIGHTED_TAG},
+    util::is_hidden,
 };
 
 const DEFAULT_LIMIT: usize = 100;
 const MAX_LIMIT: usize = 500;
 
+type TagCursor = (Vec<u8>, u16, ContractId);
+type WeightedContract = (ContractId, u16);
+
 pub fn query_contracts_with_tag(
     ctx: ReadonlyContext,
     params: ContractsByTagQueryParams,
@@ -22,7 +26,7 @@ pub fn query_contracts_with_tag(
     // Normalize limit within acceptable range
     let limit = params
         .limit
-        .and_then(|x| Some((x as usize).clamp(1, MAX_LIMIT)))
+        .map(|x| (x as usize).clamp(1, MAX_LIMIT))
         .unwrap_or(DEFAULT_LIMIT);
 
     // Get a vec of queried contract ID's
@@ -47,7 +51,7 @@ fn scan_tag(
     store: &dyn Storage,
     params: &ContractsByTagQueryParams,
     limit: usize,
-) -> Result<(Vec<(ContractId, u16)>, Option<(Vec<u8>, u16, ContractId)>), ContractError> {
+) -> Result<(Vec<WeightedContract>, Option<TagCursor>), ContractError> {
     // Convert tag to u8 slice
     let bytes_vec = IndexValue::String(params.tag.clone()).to_bytes();
     let bytes = bytes_vec.as_slice();
@@ -77,9 +81,9 @@ fn scan_tag(
     let to_bound = match &params.max_weight {
         Some(bound) => {
             let id = if desc { ContractId::MIN } else { ContractId::MAX };
-            Some(match bound {
-                &TagWeightRangeBound::Exclusive(w) => Bound::Exclusive(((bytes, w, id), PhantomData)),
-                &TagWeightRangeBound::Inclusive(w) => Bound::Inclusive(((bytes, w, id), PhantomData)),
+            Some(match *bound {
+                TagWeightRangeBound::Exclusive(w) => Bound::Exclusive(((bytes, w, id), PhantomData)),
+                TagWeightRangeBound::Inclusive(w) => Bound::Inclusive(((bytes, w, id), PhantomData)),
             })
         },
         None => None,
@@ -92,16 +96,21 @@ fn scan_tag(
         (from_bound, to_bound, Order::Ascending)
     };
 
-    // Load contract IDs and weights, ordered by weight
+    // Load contract IDs and weights, ordered by weight, skipping over hidden
+    // contracts unless explicitly requested
+    let include_hidden = params.include_hidden.unwrap_or_default();
     let keys: Vec<_> = IX_WEIGHTED_TAG
         .keys(store, min_bound, max_bound, order)
+        .filter_map(|r| {
+            r.and_then(|k| Ok((include_hidden || !is_hidden(store, k.2)?).then_some(k)))
+                .transpose()
+        })
         .take(limit)
-        .map(|r| r.unwrap())
-        .collect();
+        .collect::<StdResult<_>>()?;
 
-    let contract_ids: Vec<(ContractId, u16)> = keys.iter().map(|k| (k.2, k.1)).collect();
+    let contract_ids: Vec<WeightedContract> = keys.iter().map(|k| (k.2, k.1)).collect();
     let cursor = if keys.len() == limit {
-        keys.last().and_then(|(a, b, c)| Some((a.to_vec(), *b, *c)))
+        keys.last().map(|(a, b, c)| (a.to_vec(), *b, *c))
     } else {
         None
     };
@@ -0,0 +1,56 @@
+use cosmwasm_std::Addr;
+
+use crate::{
+    error::ContractError,
+    msg::CreatorQuotaResponse,
+    state::storage::CONFIG_CREATION_LIMITS,
+    util::{is_exempt, load_n_created, load_window},
+};
+
+use super::ReadonlyContext;
+
+pub fn query_creator_quota(
+    ctx: ReadonlyContext,
+    creator: Addr,
+) -> Result<CreatorQuotaResponse, ContractError> {
+    let ReadonlyContext { deps, env } = ctx;
+    let limits = CONFIG_CREATION_LIMITS.may_load(deps.storage)?.unwrap_or_default();
+    let is_exempt = is_exempt(deps.storage, &creator)?;
+    let n_created = load_n_created(deps.storage, &creator)?;
+
+    let window = if let Some(limit) = &limits.window {
+        Some(load_window(deps.storage, env.block.time, &creator, limit)?)
+    } else {
+        None
+    };
+
+    let n_remaining = if is_exempt {
+        None
+    } else {
+        let lifetime_remaining = limits.lifetime.map(|max| max.saturating_sub(n_created));
+        let window_remaining = limits
+            .window
+            .as_ref()
+            .zip(window.as_ref())
+            .map(|(limit, window)| limit.max.saturating_sub(window.n_created));
+        match (lifetime_remaining, window_remaining) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    };
+
+    Ok(CreatorQuotaResponse {
+        creator,
+        is_exempt,
+        n_created,
+        lifetime_limit: limits.lifetime,
+        n_created_in_window: window.as_ref().map(|x| x.n_created).unwrap_or_default(),
+        window_limit: limits.window.as_ref().map(|x| x.max),
+        window_resets_at: limits
+            .window
+            .as_ref()
+            .zip(window.as_ref())
+            .map(|(limit, window)| window.started_at.plus_seconds(limit.seconds)),
+        n_remaining,
+    })
+}
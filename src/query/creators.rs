@@ -0,0 +1,90 @@
+use std::marker::PhantomData;
+
+use cosmwasm_std::{Addr, Order};
+use cw_storage_plus::{Bound, Map};
+
+use crate::{
+    error::ContractError,
+    msg::{CreatorListResponse, CreatorPolicyPaginationResponse},
+    query::ReadonlyContext,
+    state::{
+        models::{CreatorPolicyScope, ScopedCreatorPolicy},
+        storage::{CONFIG_CREATOR_ALLOWLIST, CONFIG_CREATOR_DENYLIST, CONFIG_CREATOR_POLICIES},
+    },
+};
+
+const PAGE_SIZE: usize = 50;
+
+pub fn query_creator_policies(
+    ctx: ReadonlyContext,
+    cursor: Option<CreatorPolicyScope>,
+) -> Result<CreatorPolicyPaginationResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+
+    let cursor_key = cursor.map(|x| x.to_key());
+    let min_bound = cursor_key.as_deref().map(Bound::exclusive);
+
+    let policies = CONFIG_CREATOR_POLICIES
+        .range(deps.storage, min_bound, None, Order::Ascending)
+        .take(PAGE_SIZE)
+        .map(|r| r.map(|(_, policy)| policy))
+        .collect::<Result<Vec<ScopedCreatorPolicy>, _>>()?;
+
+    Ok(CreatorPolicyPaginationResponse {
+        cursor: if policies.len() == PAGE_SIZE {
+            policies.last().map(|x| x.scope.to_owned())
+        } else {
+            None
+        },
+        policies,
+    })
+}
+
+pub fn query_creator_allowlist(
+    ctx: ReadonlyContext,
+    scope: CreatorPolicyScope,
+    cursor: Option<Addr>,
+) -> Result<CreatorListResponse, ContractError> {
+    paginate_creator_list(ctx, CONFIG_CREATOR_ALLOWLIST, scope, cursor)
+}
+
+pub fn query_creator_denylist(
+    ctx: ReadonlyContext,
+    scope: CreatorPolicyScope,
+    cursor: Option<Addr>,
+) -> Result<CreatorListResponse, ContractError> {
+    paginate_creator_list(ctx, CONFIG_CREATOR_DENYLIST, scope, cursor)
+}
+
+fn paginate_creator_list(
+    ctx: ReadonlyContext,
+    list: Map<(&str, &Addr), u8>,
+    scope: CreatorPolicyScope,
+    cursor: Option<Addr>,
+) -> Result<CreatorListResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+    let scope_key = scope.to_key();
+
+    let mut boxed_addr: Box<Addr> = Box::new(Addr::unchecked(""));
+    let min_bound = if let Some(addr) = cursor {
+        *boxed_addr = addr;
+        Some(Bound::Exclusive((boxed_addr.as_ref(), PhantomData)))
+    } else {
+        None
+    };
+
+    let creators = list
+        .prefix(&scope_key)
+        .keys(deps.storage, min_bound, None, Order::Ascending)
+        .take(PAGE_SIZE)
+        .collect::<Result<Vec<Addr>, _>>()?;
+
+    Ok(CreatorListResponse {
+        cursor: if creators.len() == PAGE_SIZE {
+            creators.last().cloned()
+        } else {
+            None
+        },
+        creators,
+    })
+}
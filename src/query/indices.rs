@@ -0,0 +1,73 @@
+use std::marker::PhantomData;
+
+use cosmwasm_std::Order;
+use cw_storage_plus::Bound;
+
+use crate::{
+    error::ContractError,
+    msg::{CustomIndexPaginationResponse, CustomIndexResponse},
+    query::ReadonlyContext,
+    state::{models::CustomIndex, storage::CONFIG_CUSTOM_INDICES},
+};
+
+const PAGE_SIZE: usize = 50;
+
+pub fn query_index(
+    ctx: ReadonlyContext,
+    name: String,
+) -> Result<CustomIndexResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+    let index = CONFIG_CUSTOM_INDICES.load(deps.storage, &name)?;
+
+    Ok(CustomIndexResponse {
+        name,
+        kind: index.kind,
+        description: index.description,
+        writable_by_contracts: index.writable_by_contracts,
+    })
+}
+
+pub fn query_paginated_indices(
+    ctx: ReadonlyContext,
+    cursor: Option<String>,
+) -> Result<CustomIndexPaginationResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+
+    let mut boxed_name: Box<String> = Box::default();
+    let min_bound = if let Some(s) = cursor {
+        *boxed_name = s;
+        Some(Bound::Exclusive((boxed_name.as_ref(), PhantomData)))
+    } else {
+        None
+    };
+
+    let mut index_resps: Vec<CustomIndexResponse> = Vec::with_capacity(PAGE_SIZE);
+    for result in CONFIG_CUSTOM_INDICES
+        .range(deps.storage, min_bound, None, Order::Ascending)
+        .take(PAGE_SIZE)
+    {
+        let (
+            name,
+            CustomIndex {
+                kind,
+                description,
+                writable_by_contracts,
+            },
+        ) = result?;
+        index_resps.push(CustomIndexResponse {
+            name,
+            kind,
+            description,
+            writable_by_contracts,
+        });
+    }
+
+    Ok(CustomIndexPaginationResponse {
+        cursor: if index_resps.len() == PAGE_SIZE {
+            index_resps.last().map(|x| x.name.to_owned())
+        } else {
+            None
+        },
+        indices: index_resps,
+    })
+}
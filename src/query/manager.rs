@@ -0,0 +1,8 @@
+use crate::{error::ContractError, msg::PendingManagerResponse, state::storage::PENDING_MANAGER};
+
+use super::ReadonlyContext;
+
+pub fn query_pending_manager(ctx: ReadonlyContext) -> Result<PendingManagerResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+    Ok(PendingManagerResponse(PENDING_MANAGER.may_load(deps.storage)?))
+}
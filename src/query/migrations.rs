@@ -1,15 +1,28 @@
-use cosmwasm_std::Order;
+use std::marker::PhantomData;
+
+use cosmwasm_std::{Addr, Binary, Order, Storage, Uint64};
+use cw_storage_plus::Bound;
 
 use crate::{
     error::ContractError,
-    msg::MigrationSessionResponse,
+    execute::migrate::{normalize_params, scan_migration_targets},
+    msg::{
+        MigrationHistoryResponse, MigrationParams, MigrationPreviewEntry, MigrationPreviewResponse,
+        MigrationResultsResponse, MigrationSessionResponse,
+    },
     query::ReadonlyContext,
     state::{
-        models::{Migration, MigrationError},
-        storage::{MIGRATIONS, MIGRATION_ERRORS},
+        models::{Migration, MigrationError, MigrationSessionRecord},
+        storage::{
+            IX_MIGRATION_RESULTS_BY_CONTRACT, IX_MIGRATION_RESULTS_BY_SESSION, MIGRATIONS, MIGRATION_ERRORS,
+            MIGRATION_HISTORY, MIGRATION_NAME_2_SESSION_ID, MIGRATION_RESULTS, MIGRATION_ROLLBACKS,
+            MIGRATION_ROLLBACK_ERRORS,
+        },
     },
 };
 
+const PAGE_SIZE: usize = 50;
+
 pub fn query_migration_session(
     ctx: ReadonlyContext,
     session_name: String,
@@ -31,7 +44,16 @@ pub fn query_migration_session(
         .map(|r| r.unwrap().1)
         .collect();
 
+    let rollback = MIGRATION_ROLLBACKS.may_load(deps.storage, &session_name)?;
+
+    let rollback_errors: Vec<MigrationError> = MIGRATION_ROLLBACK_ERRORS
+        .prefix(&session_name)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|r| r.unwrap().1)
+        .collect();
+
     Ok(MigrationSessionResponse {
+        session_id: MIGRATION_NAME_2_SESSION_ID.may_load(deps.storage, &session_name)?,
         cursor,
         params,
         status,
@@ -39,5 +61,127 @@ pub fn query_migration_session(
         n_error,
         n_success,
         errors,
+        rollback,
+        rollback_errors,
+    })
+}
+
+/// List the next batch of contracts that stepping through a migration session
+/// with the given params would migrate, resuming from the given cursor.
+pub fn query_migration_preview(
+    ctx: ReadonlyContext,
+    params: MigrationParams,
+    cursor: Option<Binary>,
+) -> Result<MigrationPreviewResponse, ContractError> {
+    let ReadonlyContext { deps, env } = ctx;
+    let mut params = params;
+
+    normalize_params(deps.storage, &mut params)?;
+
+    let (targets, cursor) = scan_migration_targets(deps.storage, &params, cursor.as_ref(), params.batch_size.unwrap())?;
+
+    let mut contracts: Vec<MigrationPreviewEntry> = Vec::with_capacity(targets.len());
+
+    for (_, address) in targets {
+        let contract_info = deps.querier.query_wasm_contract_info(address.to_owned())?;
+        let admin = contract_info.admin.map(Addr::unchecked);
+        contracts.push(MigrationPreviewEntry {
+            address,
+            code_id: contract_info.code_id.into(),
+            is_factory_admin: admin.as_ref() == Some(&env.contract.address),
+            admin,
+        });
+    }
+
+    Ok(MigrationPreviewResponse {
+        to_code_id: params.to_code_id.unwrap(),
+        contracts,
+        cursor,
+    })
+}
+
+pub fn query_migration_history(
+    ctx: ReadonlyContext,
+    cursor: Option<u32>,
+) -> Result<MigrationHistoryResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+
+    let min_bound = cursor.map(Bound::exclusive);
+
+    let sessions = MIGRATION_HISTORY
+        .range(deps.storage, min_bound, None, Order::Ascending)
+        .take(PAGE_SIZE)
+        .map(|r| r.map(|(_, record)| record))
+        .collect::<Result<Vec<MigrationSessionRecord>, _>>()?;
+
+    Ok(MigrationHistoryResponse {
+        cursor: if sessions.len() == PAGE_SIZE {
+            sessions.last().map(|x| x.id)
+        } else {
+            None
+        },
+        sessions,
+    })
+}
+
+pub fn query_historical_migration_session(
+    ctx: ReadonlyContext,
+    session_id: u32,
+) -> Result<MigrationSessionRecord, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+    Ok(MIGRATION_HISTORY.load(deps.storage, session_id)?)
+}
+
+pub fn query_migration_session_results(
+    ctx: ReadonlyContext,
+    session_id: u32,
+    cursor: Option<Uint64>,
+) -> Result<MigrationResultsResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+
+    let min_bound = cursor.map(|x| Bound::Exclusive((x.u64(), PhantomData)));
+
+    let result_ids = IX_MIGRATION_RESULTS_BY_SESSION
+        .prefix(session_id)
+        .keys(deps.storage, min_bound, None, Order::Ascending)
+        .take(PAGE_SIZE)
+        .collect::<Result<Vec<u64>, _>>()?;
+
+    load_results(deps.storage, result_ids)
+}
+
+pub fn query_contract_migration_results(
+    ctx: ReadonlyContext,
+    address: Addr,
+    cursor: Option<Uint64>,
+) -> Result<MigrationResultsResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+
+    let min_bound = cursor.map(|x| Bound::Exclusive((x.u64(), PhantomData)));
+
+    let result_ids = IX_MIGRATION_RESULTS_BY_CONTRACT
+        .prefix(&address)
+        .keys(deps.storage, min_bound, None, Order::Ascending)
+        .take(PAGE_SIZE)
+        .collect::<Result<Vec<u64>, _>>()?;
+
+    load_results(deps.storage, result_ids)
+}
+
+fn load_results(
+    store: &dyn Storage,
+    result_ids: Vec<u64>,
+) -> Result<MigrationResultsResponse, ContractError> {
+    let mut results = Vec::with_capacity(result_ids.len());
+    for result_id in result_ids.iter() {
+        results.push(MIGRATION_RESULTS.load(store, *result_id)?);
+    }
+    Ok(MigrationResultsResponse {
+        cursor: if result_ids.len() == PAGE_SIZE {
+            result_ids.last().map(|x| (*x).into())
+        } else {
+            None
+        },
+        results,
     })
 }
@@ -1,8 +1,16 @@
+pub mod code_ids;
 pub mod config;
 pub mod contract;
 pub mod contracts;
+pub mod creator_quota;
+pub mod creators;
+pub mod indices;
+pub mod manager;
 pub mod migrations;
+pub mod pending_config;
+pub mod predict_address;
 pub mod presets;
+pub mod roles;
 
 use cosmwasm_std::{Deps, Env};
 
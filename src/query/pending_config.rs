@@ -0,0 +1,8 @@
+use crate::{error::ContractError, msg::PendingConfigResponse, state::storage::PENDING_CONFIG};
+
+use super::ReadonlyContext;
+
+pub fn query_pending_config(ctx: ReadonlyContext) -> Result<PendingConfigResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+    Ok(PendingConfigResponse(PENDING_CONFIG.may_load(deps.storage)?))
+}
@@ -0,0 +1,31 @@
+use cosmwasm_std::instantiate2_address;
+
+use crate::{
+    error::ContractError,
+    msg::{PredictAddressQueryParams, PredictAddressResponse},
+    query::ReadonlyContext,
+    util::{resolve_code_id, validate_salt},
+};
+
+/// Compute the address of a contract created through the factory with the
+/// given code ID or channel (or default) and salt.
+pub fn query_predict_address(
+    ctx: ReadonlyContext,
+    params: PredictAddressQueryParams,
+) -> Result<PredictAddressResponse, ContractError> {
+    let ReadonlyContext { deps, env } = ctx;
+    let PredictAddressQueryParams { code_id, channel, salt } = params;
+
+    validate_salt(&salt)?;
+
+    let code_id = resolve_code_id(deps.storage, code_id, channel.as_ref())?;
+    let checksum = deps.querier.query_wasm_code_info(code_id.u64())?.checksum;
+    let creator = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+    let address = instantiate2_address(checksum.as_slice(), &creator, salt.as_slice())
+        .map_err(|e| ContractError::ValidationError { reason: e.to_string() })?;
+
+    Ok(PredictAddressResponse {
+        address: deps.api.addr_humanize(&address)?,
+        code_id,
+    })
+}
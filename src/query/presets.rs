@@ -31,7 +31,7 @@ pub fn query_paginated_presets(
 ) -> Result<PresetPaginationResponse, ContractError> {
     let ReadonlyContext { deps, .. } = ctx;
 
-    let mut boxed_name: Box<String> = Box::new(String::new());
+    let mut boxed_name: Box<String> = Box::default();
     let min_bound = if let Some(s) = cursor {
         *boxed_name = s;
         Some(Bound::Exclusive((boxed_name.as_ref(), PhantomData)))
@@ -58,7 +58,7 @@ pub fn query_paginated_presets(
     }
 
     Ok(PresetPaginationResponse {
-        cursor: preset_resps.last().and_then(|x| Some(x.name.to_owned())),
+        cursor: preset_resps.last().map(|x| x.name.to_owned()),
         presets: preset_resps,
     })
 }
@@ -0,0 +1,66 @@
+use std::marker::PhantomData;
+
+use cosmwasm_std::{Addr, Order};
+use cw_storage_plus::Bound;
+
+use crate::{
+    error::ContractError,
+    msg::{AddressRolesResponse, RoleMembersResponse},
+    query::ReadonlyContext,
+    state::{
+        models::Role,
+        storage::{MANAGED_BY, ROLE_MEMBERS},
+    },
+};
+
+const PAGE_SIZE: usize = 50;
+
+pub fn query_role_members(
+    ctx: ReadonlyContext,
+    role: Role,
+    cursor: Option<Addr>,
+) -> Result<RoleMembersResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+
+    let mut boxed_addr: Box<Addr> = Box::new(Addr::unchecked(""));
+    let min_bound = if let Some(addr) = cursor {
+        *boxed_addr = addr;
+        Some(Bound::Exclusive((boxed_addr.as_ref(), PhantomData)))
+    } else {
+        None
+    };
+
+    let members = ROLE_MEMBERS
+        .prefix(role.as_str())
+        .keys(deps.storage, min_bound, None, Order::Ascending)
+        .take(PAGE_SIZE)
+        .collect::<Result<Vec<Addr>, _>>()?;
+
+    Ok(RoleMembersResponse {
+        role,
+        cursor: if members.len() == PAGE_SIZE {
+            members.last().cloned()
+        } else {
+            None
+        },
+        members,
+    })
+}
+
+pub fn query_address_roles(
+    ctx: ReadonlyContext,
+    address: Addr,
+) -> Result<AddressRolesResponse, ContractError> {
+    let ReadonlyContext { deps, .. } = ctx;
+
+    let roles = Role::ALL
+        .into_iter()
+        .filter(|role| ROLE_MEMBERS.has(deps.storage, (role.as_str(), &address)))
+        .collect();
+
+    Ok(AddressRolesResponse {
+        is_manager: address == MANAGED_BY.load(deps.storage)?,
+        address,
+        roles,
+    })
+}
@@ -1,38 +1,72 @@
 pub mod models;
 pub mod storage;
+pub mod upgrade;
 
 use cosmwasm_std::{Response, Uint64};
 use storage::{
-    CONFIG_ALLOWED_CODE_IDS, CONFIG_DEFAULT_CODE_ID, CONTRACT_COUNTER, CONTRACT_ID_COUNTER, CREATED_AT, CREATED_BY,
-    MANAGED_BY, REPLY_ID_COUNTER,
+    CONFIG_DEFAULT_CODE_ID, CONFIG_DELAY, CONTRACT_COUNTER, CONTRACT_ID_COUNTER, CREATED_AT, CREATED_BY, MANAGED_BY,
+    PAUSE_STATE, REPLY_ID_COUNTER, STATE_VERSION,
 };
+use upgrade::LATEST_STATE_VERSION;
 
-use crate::{error::ContractError, execute::Context, msg::InstantiateMsg};
+use crate::{
+    error::ContractError,
+    execute::{
+        set_config::{save_creation_fees, save_creation_limits},
+        Context,
+    },
+    msg::InstantiateMsg,
+    util::allow_code_id,
+};
+use models::Config;
 
 /// Top-level initialization of contract state
 pub fn init(
     ctx: Context,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    let Context { deps, env, info } = ctx;
+    let Context { mut deps, env, info } = ctx;
     let InstantiateMsg { config } = msg;
+    let Config {
+        managed_by,
+        default_code_id,
+        allowed_code_ids,
+        treasury,
+        code_id_fees,
+        preset_fees,
+        creation_limits,
+        config_delay,
+        pause,
+    } = config;
 
     REPLY_ID_COUNTER.save(deps.storage, &Uint64::zero())?;
     CONTRACT_ID_COUNTER.save(deps.storage, &0)?;
     CONTRACT_COUNTER.save(deps.storage, &0)?;
-    MANAGED_BY.save(deps.storage, &deps.api.addr_validate(config.managed_by.as_str())?)?;
+    MANAGED_BY.save(deps.storage, &deps.api.addr_validate(managed_by.as_str())?)?;
     CREATED_BY.save(deps.storage, &info.sender)?;
     CREATED_AT.save(deps.storage, &env.block.time)?;
+    STATE_VERSION.save(deps.storage, &LATEST_STATE_VERSION)?;
 
-    for code_id in config.allowed_code_ids.iter() {
-        CONFIG_ALLOWED_CODE_IDS.save(deps.storage, code_id.u64(), &0)?;
+    for code_id in allowed_code_ids.unwrap_or_default().iter() {
+        allow_code_id(deps.storage, *code_id)?;
     }
 
-    if let Some(default_code_id) = config.default_code_id {
-        CONFIG_ALLOWED_CODE_IDS.save(deps.storage, default_code_id.u64(), &0)?;
+    if let Some(default_code_id) = default_code_id {
+        allow_code_id(deps.storage, default_code_id)?;
         CONFIG_DEFAULT_CODE_ID.save(deps.storage, &default_code_id)?;
     }
 
+    save_creation_fees(deps.branch(), treasury, code_id_fees, preset_fees)?;
+    save_creation_limits(deps.branch(), creation_limits)?;
+
+    if let Some(config_delay) = config_delay {
+        CONFIG_DELAY.save(deps.storage, &config_delay)?;
+    }
+
+    if let Some(pause) = pause {
+        PAUSE_STATE.save(deps.storage, &pause)?;
+    }
+
     Ok(Response::new().add_attribute("action", "instantiate"))
 }
 
@@ -1,8 +1,10 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint64};
+use cosmwasm_std::{Addr, Binary, Coin, HexBinary, Timestamp, Uint128, Uint64};
+use cw20::Denom;
+use cw_utils::Expiration;
 use serde_json::{Map as SerdeMap, Value};
 
-use crate::msg::MigrationParams;
+use crate::msg::{IndexUpdate, IndexValueKind, MigrationParams, RelationUpdate, TagUpdate};
 
 use super::storage::ContractId;
 
@@ -10,7 +12,186 @@ use super::storage::ContractId;
 pub struct Config {
     pub managed_by: Addr,
     pub default_code_id: Option<Uint64>,
-    pub allowed_code_ids: Vec<Uint64>,
+    /// Replaces the allowed code IDs when set. Not returned by the Config
+    /// query; see the paginated AllowedCodeIds query instead.
+    pub allowed_code_ids: Option<Vec<Uint64>>,
+    /// Recipient of creation fees, defaulting to the manager.
+    pub treasury: Option<Addr>,
+    pub code_id_fees: Option<Vec<CodeIdFee>>,
+    pub preset_fees: Option<Vec<PresetFee>>,
+    pub creation_limits: Option<CreationLimits>,
+    /// Min seconds between enqueuing a config change and applying it. Once
+    /// non-zero, config changes can't be applied immediately.
+    pub config_delay: Option<u64>,
+    /// Set through Pause and Unpause; ignored by Configure
+    pub pause: Option<PauseState>,
+}
+
+/// A config change enqueued by Configure or a code ID patch operation,
+/// applicable after a delay.
+#[cw_serde]
+pub struct PendingConfig {
+    pub change: ConfigChange,
+    pub execute_after: Timestamp,
+    pub proposed_by: Addr,
+}
+
+#[cw_serde]
+pub enum ConfigChange {
+    Configure(Config),
+    AllowCodeIds { code_ids: Vec<Uint64> },
+    DisallowCodeIds { code_ids: Vec<Uint64> },
+    SetDefaultCodeId { code_id: Option<Uint64> },
+    SetChannel { name: String, code_id: Option<Uint64> },
+}
+
+/// Functions of the factory that can be paused independently.
+#[cw_serde]
+#[derive(Copy)]
+pub enum PauseSwitch {
+    /// Creating contracts
+    Create,
+    /// Updates sent by contracts themselves
+    Update,
+    Migrations,
+    Presets,
+}
+
+#[cw_serde]
+pub struct PauseState {
+    pub switches: Vec<PauseSwitch>,
+    pub reason: Option<String>,
+}
+
+/// Limits on the number of contracts each creator may create. The manager and
+/// exempted creators aren't subject to them.
+#[cw_serde]
+#[derive(Default)]
+pub struct CreationLimits {
+    /// Max number of contracts any one creator may create through the factory,
+    /// counting contracts since deregistered
+    pub lifetime: Option<u32>,
+    pub window: Option<CreationWindowLimit>,
+}
+
+/// Max number of contracts any one creator may create in a fixed time window.
+#[cw_serde]
+pub struct CreationWindowLimit {
+    pub seconds: u64,
+    pub max: u32,
+}
+
+#[cw_serde]
+pub struct CreationWindow {
+    pub started_at: Timestamp,
+    pub n_created: u32,
+}
+
+/// Registry entry of an allowed code ID.
+#[cw_serde]
+#[derive(Default)]
+pub struct CodeIdMetadata {
+    pub name: Option<String>,
+    /// Semantic version of the code
+    pub version: Option<String>,
+    pub description: Option<String>,
+    /// Checksum of the wasm code, verified against the chain when set
+    pub checksum: Option<HexBinary>,
+    /// Deprecated code IDs can't be used to create new contracts
+    pub deprecated: bool,
+}
+
+#[cw_serde]
+pub struct CreationFee {
+    pub denom: Denom,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct CodeIdFee {
+    pub code_id: Uint64,
+    pub fee: CreationFee,
+}
+
+#[cw_serde]
+pub struct PresetFee {
+    pub preset: String,
+    pub fee: CreationFee,
+}
+
+#[cw_serde]
+pub struct PendingManager {
+    pub address: Addr,
+    pub expiry: Option<Expiration>,
+}
+
+/// Permissions that the manager may delegate to other addresses.
+#[cw_serde]
+#[derive(Copy)]
+pub enum Role {
+    /// Update the config, creator quotas and creator policies
+    ConfigAdmin,
+    /// Set and remove presets
+    PresetEditor,
+    /// Run migrations
+    Migrator,
+    /// Manage custom indices and import contracts
+    Indexer,
+    /// Update, hide and deregister any contract
+    Moderator,
+}
+
+impl Role {
+    pub const ALL: [Role; 5] = [
+        Role::ConfigAdmin,
+        Role::PresetEditor,
+        Role::Migrator,
+        Role::Indexer,
+        Role::Moderator,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ConfigAdmin => "config_admin",
+            Self::PresetEditor => "preset_editor",
+            Self::Migrator => "migrator",
+            Self::Indexer => "indexer",
+            Self::Moderator => "moderator",
+        }
+    }
+}
+
+#[cw_serde]
+pub enum CreatorPolicy {
+    Open,
+    AllowlistOnly,
+    Denylist,
+}
+
+/// What a creator policy applies to. The policy of a contract's preset takes
+/// precedence over that of its code ID, which takes precedence over the global
+/// policy.
+#[cw_serde]
+pub enum CreatorPolicyScope {
+    Global,
+    CodeId(Uint64),
+    Preset(String),
+}
+
+impl CreatorPolicyScope {
+    pub fn to_key(&self) -> String {
+        match self {
+            Self::Global => String::new(),
+            Self::CodeId(code_id) => format!("code_id:{}", code_id),
+            Self::Preset(preset) => format!("preset:{}", preset),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct ScopedCreatorPolicy {
+    pub scope: CreatorPolicyScope,
+    pub policy: CreatorPolicy,
 }
 
 #[cw_serde]
@@ -19,7 +200,23 @@ pub struct SubMsgContext {
     pub contract_id: ContractId,
     pub created_by: Addr,
     pub name: Option<String>,
-    pub admin: Addr,
+    pub label: Option<String>,
+    pub admin: Option<Addr>,
+    pub batch_id: Option<u64>,
+    pub indices: Vec<IndexUpdate>,
+    pub relations: Vec<RelationUpdate>,
+    pub tags: Vec<TagUpdate>,
+    pub callback: bool,
+    /// Creation fee and funds held for the contract until its reply
+    pub fee: Option<CreationFee>,
+    pub funds: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct CreationBatch {
+    pub size: u32,
+    pub addresses: Vec<Option<Addr>>,
+    pub best_effort: bool,
 }
 
 #[cw_serde]
@@ -29,23 +226,73 @@ pub struct Preset {
     pub n_uses: u32,
 }
 
+#[cw_serde]
+pub struct CustomIndex {
+    pub kind: IndexValueKind,
+    pub description: Option<String>,
+    pub writable_by_contracts: bool,
+}
+
 #[cw_serde]
 pub enum MigrationStatus {
     Running,
     Complete,
     Aborted,
+    Cancelled,
 }
 
 #[cw_serde]
 pub struct Migration {
     pub params: MigrationParams,
     pub status: MigrationStatus,
-    pub cursor: Option<ContractId>,
+    /// Raw key of the last contract visited in the target's index
+    pub cursor: Option<Binary>,
     pub retry_cursor: Option<ContractId>,
     pub n_success: u32,
     pub n_error: u32,
 }
 
+/// Permanent record of a migration session, kept after the session itself is
+/// cancelled or its name is reused.
+#[cw_serde]
+pub struct MigrationSessionRecord {
+    pub id: u32,
+    pub name: String,
+    pub started_by: Addr,
+    pub params: MigrationParams,
+    pub started_at_height: u64,
+    /// Height at which the session last completed, was rolled back or cancelled
+    pub ended_at_height: Option<u64>,
+    pub n_success: u32,
+    pub n_error: u32,
+    pub status: MigrationStatus,
+}
+
+/// Outcome of migrating one contract, through a session, a session's rollback
+/// or on its own.
+#[cw_serde]
+pub struct MigrationResult {
+    pub session_id: Option<u32>,
+    pub contract: Addr,
+    /// Code ID the contract ran beforehand, if the migration succeeded
+    pub prev_code_id: Option<Uint64>,
+    pub code_id: Uint64,
+    pub is_rollback: bool,
+    pub error: Option<String>,
+    pub height: u64,
+}
+
+/// Rollbacks walk the contracts a session migrated successfully, moving each
+/// back to the code ID it ran beforehand. Contracts are forgotten once rolled
+/// back, so another pass after completion retries only those that failed.
+#[cw_serde]
+pub struct MigrationRollback {
+    pub status: MigrationStatus,
+    pub cursor: Option<ContractId>,
+    pub n_success: u32,
+    pub n_error: u32,
+}
+
 #[cw_serde]
 pub struct MigrationError {
     pub contract: Addr,
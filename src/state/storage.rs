@@ -3,37 +3,74 @@ use cw_storage_plus::{Item, Map};
 
 use crate::msg::IndexValue;
 
-use super::models::{Migration, MigrationError, Preset, SubMsgContext};
+use super::models::{
+    CodeIdMetadata, CreationBatch, CreationFee, CreationLimits, CreationWindow, CustomIndex, Migration, MigrationError,
+    MigrationResult, MigrationRollback, MigrationSessionRecord, PauseState, PendingConfig, PendingManager, Preset,
+    ScopedCreatorPolicy, SubMsgContext,
+};
 
 pub type ContractId = u32;
 pub type IndexMap<'a> = Map<'a, (&'a [u8], ContractId), u8>;
+pub type RelationMap<'a> = Map<'a, (ContractId, &'a [u8], &'a [u8]), Option<IndexValue>>;
 
 // Base contract metadata
 pub const MANAGED_BY: Item<Addr> = Item::new("managed_by");
+pub const PENDING_CONFIG: Item<PendingConfig> = Item::new("pending_config");
+pub const PAUSE_STATE: Item<PauseState> = Item::new("pause_state");
+pub const PENDING_MANAGER: Item<PendingManager> = Item::new("pending_manager");
+pub const ROLE_MEMBERS: Map<(&str, &Addr), u8> = Map::new("role_members");
 pub const CREATED_BY: Item<Addr> = Item::new("created_by");
 pub const CREATED_AT: Item<Timestamp> = Item::new("created_at");
 
+/// Version of the storage layout, bumped whenever migrate must repair state
+pub const STATE_VERSION: Item<u16> = Item::new("state_version");
+
 // pub const CONFIG_MAX_SIZEOF_STRING: Item<u16> = Item::new("max_sizeof_string");
 pub const CONFIG_DEFAULT_CODE_ID: Item<Uint64> = Item::new("default_code_id");
-pub const CONFIG_ALLOWED_CODE_IDS: Map<u64, u8> = Map::new("allowed_code_ids");
+pub const CONFIG_ALLOWED_CODE_IDS: Map<u64, CodeIdMetadata> = Map::new("allowed_code_ids");
+pub const CONFIG_CHANNELS: Map<&String, Uint64> = Map::new("channels");
+pub const CONFIG_CREATOR_POLICIES: Map<&str, ScopedCreatorPolicy> = Map::new("creator_policies");
+pub const CONFIG_CREATOR_ALLOWLIST: Map<(&str, &Addr), u8> = Map::new("creator_allowlist");
+pub const CONFIG_CREATOR_DENYLIST: Map<(&str, &Addr), u8> = Map::new("creator_denylist");
+pub const CONFIG_TREASURY: Item<Addr> = Item::new("treasury");
+pub const CONFIG_CODE_ID_FEES: Map<u64, CreationFee> = Map::new("code_id_fees");
+pub const CONFIG_PRESET_FEES: Map<&String, CreationFee> = Map::new("preset_fees");
+pub const CONFIG_CREATION_LIMITS: Item<CreationLimits> = Item::new("creation_limits");
+pub const CONFIG_DELAY: Item<u64> = Item::new("config_delay");
+pub const CREATOR_QUOTA_EXEMPTIONS: Map<&Addr, bool> = Map::new("creator_quota_exemptions");
+pub const CREATOR_WINDOWS: Map<&Addr, CreationWindow> = Map::new("creator_windows");
+/// Number of contracts each creator has created through the factory, which
+/// deregistering a contract doesn't decrease
+pub const CREATOR_N_CREATED: Map<&Addr, u32> = Map::new("creator_n_created");
+
+/// Registry of custom indices that contracts may be indexed by
+pub const CONFIG_CUSTOM_INDICES: Map<&String, CustomIndex> = Map::new("custom_indices");
 
 // ID generators for various program entities
 pub const REPLY_ID_COUNTER: Item<Uint64> = Item::new("reply_id_counter");
 pub const CONTRACT_ID_COUNTER: Item<ContractId> = Item::new("contract_id_counter");
+pub const MIGRATION_SESSION_ID_COUNTER: Item<u32> = Item::new("migration_session_id_counter");
+pub const MIGRATION_RESULT_ID_COUNTER: Item<u64> = Item::new("migration_result_id_counter");
 
 /// Temp storage for data needed between executions and their replies
 pub const SUBMSG_CONTEXTS: Map<u64, SubMsgContext> = Map::new("submsg_contexts");
 
+/// Temp storage for tracking the outcome of each contract in a CreateBatch
+pub const SUBMSG_BATCHES: Map<u64, CreationBatch> = Map::new("submsg_batches");
+
 /// Total number of contracts created and managed by the factory
 pub const CONTRACT_COUNTER: Item<u32> = Item::new("contract_counter");
 
 // Built-in lookup tables for use in resolving contracts in factory
 pub const CONTRACT_ID_2_ADDR: Map<ContractId, Addr> = Map::new("contract_id_2_addr");
 pub const CONTRACT_ID_2_NAME: Map<ContractId, String> = Map::new("contract_id_2_name");
+pub const CONTRACT_ID_2_LABEL: Map<ContractId, String> = Map::new("contract_id_2_label");
 pub const CONTRACT_ADDR_2_ID: Map<&Addr, ContractId> = Map::new("contract_addr_2_id");
 pub const CONTRACT_NAME_2_ID: Map<&String, ContractId> = Map::new("contract_name_2_id");
 pub const CONTRACT_ID_2_IS_HIDDEN: Map<ContractId, bool> = Map::new("contract_id_2_is_hidden");
+pub const CONTRACT_ID_2_IS_DEREGISTERING: Map<ContractId, bool> = Map::new("contract_id_2_is_deregistering");
 pub const CONTRACT_ID_2_PARITION: Map<ContractId, u32> = Map::new("contract_id_2_partition");
+pub const CONTRACT_ID_2_PREV_CODE_ID: Map<ContractId, Uint64> = Map::new("contract_id_2_prev_code_id");
 
 /// Lookup-table for determining which "indexes" are used by a given contracjt
 pub const CONTRACT_CUSTOM_IX_VALUES: Map<(ContractId, &String), Vec<u8>> = Map::new("custom_ix_values");
@@ -58,7 +95,7 @@ pub const IX_WEIGHTED_TAG: Map<(&[u8], u16, ContractId), u8> = Map::new("ix_weig
 pub const CONTRACT_TAG_WEIGHTS: Map<(ContractId, &[u8]), u16> = Map::new("contract_tag_weights");
 
 // Contract "relations" data
-pub const IX_REL_CONTRACT_ADDR: Map<(ContractId, &[u8], &[u8]), Option<IndexValue>> = Map::new("ix_rel_contract_addr");
+pub const IX_REL_CONTRACT_ADDR: RelationMap = Map::new("ix_rel_contract_addr");
 pub const IX_REL_ADDR: Map<(&[u8], &[u8], ContractId), u8> = Map::new("ix_rel_addr");
 
 pub const PRESETS: Map<&String, Preset> = Map::new("presets");
@@ -66,3 +103,19 @@ pub const PRESETS: Map<&String, Preset> = Map::new("presets");
 pub const MIGRATIONS: Map<&String, Migration> = Map::new("migrations");
 pub const MIGRATION_REPLY_ID_2_STATE: Map<u64, (String, ContractId)> = Map::new("migration_reply_id_2_name");
 pub const MIGRATION_ERRORS: Map<(&String, ContractId), MigrationError> = Map::new("migration_errors");
+pub const MIGRATION_PREV_CODE_IDS: Map<(&String, ContractId), Uint64> = Map::new("migration_prev_code_ids");
+
+/// Progress of rolling back each migration session, with errors tracked apart
+/// from those of the migration itself.
+pub const MIGRATION_ROLLBACKS: Map<&String, MigrationRollback> = Map::new("migration_rollbacks");
+pub const MIGRATION_ROLLBACK_REPLY_ID_2_STATE: Map<u64, (String, ContractId)> =
+    Map::new("migration_rollback_reply_id_2_name");
+pub const MIGRATION_ROLLBACK_ERRORS: Map<(&String, ContractId), MigrationError> = Map::new("migration_rollback_errors");
+
+/// Append-only history of migration sessions and the per-contract results of
+/// every migration, indexed by session and by contract address
+pub const MIGRATION_HISTORY: Map<u32, MigrationSessionRecord> = Map::new("migration_history");
+pub const MIGRATION_NAME_2_SESSION_ID: Map<&String, u32> = Map::new("migration_name_2_session_id");
+pub const MIGRATION_RESULTS: Map<u64, MigrationResult> = Map::new("migration_results");
+pub const IX_MIGRATION_RESULTS_BY_SESSION: Map<(u32, u64), u8> = Map::new("ix_migration_results_by_session");
+pub const IX_MIGRATION_RESULTS_BY_CONTRACT: Map<(&Addr, u64), u8> = Map::new("ix_migration_results_by_contract");
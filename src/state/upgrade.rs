@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{attr, Addr, Binary, Order, Response, StdResult, Storage};
+use cw_storage_plus::Map;
+
+use crate::{
+    error::ContractError,
+    msg::{IndexValue, IndexValueKind, MigrateMsg, MigrationParams, MAX_SIZEOF_STRING_KEY},
+};
+
+use super::{
+    build_index_storage_key, build_reverse_mapping_storage_key,
+    models::{CodeIdMetadata, CustomIndex, Migration, MigrationStatus},
+    storage::{
+        ContractId, IndexMap, CONFIG_ALLOWED_CODE_IDS, CONFIG_CUSTOM_INDICES, CONTRACT_CUSTOM_IX_VALUES,
+        CONTRACT_ID_2_IS_HIDDEN, CONTRACT_TAG_WEIGHTS, CREATOR_N_CREATED, ID_2_ADMIN, ID_2_CODE_ID, ID_2_CREATED_AT,
+        ID_2_CREATED_BY, ID_2_UPDATED_AT, IX_ADMIN, IX_CODE_ID, IX_CREATED_AT, IX_CREATED_BY, IX_REL_ADDR,
+        IX_REL_CONTRACT_ADDR, IX_TAG, IX_UPDATED_AT, IX_WEIGHTED_TAG, MIGRATIONS, STATE_VERSION,
+    },
+};
+
+/// State version 1: index keys use the order-preserving IndexValue encoding.
+/// State version 2: custom indices must be registered with a value kind.
+/// State version 3: hidden contracts keep their entries in custom indices.
+/// State version 4: every built-in index has a complete reverse-lookup table.
+/// State version 5: allowed code IDs map to registry metadata.
+/// State version 6: migration session cursors are raw index keys.
+/// State version 7: tag weights are recorded for each contract's tags.
+/// State version 8: the number of contracts each creator has created is counted.
+pub const LATEST_STATE_VERSION: u16 = 8;
+
+/// Bring storage written by previous versions of the contract up to date. Each
+/// step runs exactly once, in order, after which the state version is bumped.
+pub fn upgrade(
+    store: &mut dyn Storage,
+    msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    let prev_version = STATE_VERSION.may_load(store)?.unwrap_or_default();
+
+    let declared_kinds: HashMap<String, IndexValueKind> = msg
+        .custom_index_kinds
+        .unwrap_or_default()
+        .into_iter()
+        .map(|x| (x.name, x.kind))
+        .collect();
+
+    if prev_version < 1 {
+        rekey_indices(store, &declared_kinds)?;
+    }
+    if prev_version < 2 {
+        register_custom_indices(store, &declared_kinds)?;
+    }
+    if prev_version < 3 {
+        relist_hidden_contracts(store)?;
+    }
+    if prev_version < 4 {
+        backfill_reverse_maps(store)?;
+    }
+    if prev_version < 5 {
+        register_allowed_code_ids(store)?;
+    }
+    if prev_version < 6 {
+        rekey_migration_cursors(store)?;
+    }
+    if prev_version < 7 {
+        rebuild_tag_weights(store)?;
+    }
+    if prev_version < 8 {
+        count_created_contracts(store)?;
+    }
+
+    STATE_VERSION.save(store, &LATEST_STATE_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate"),
+        attr("prev_state_version", prev_version.to_string()),
+        attr("state_version", LATEST_STATE_VERSION.to_string()),
+    ]))
+}
+
+/// Re-encode every index key written with the legacy little-endian encoding.
+fn rekey_indices(
+    store: &mut dyn Storage,
+    declared_kinds: &HashMap<String, IndexValueKind>,
+) -> Result<(), ContractError> {
+    let u64_kind = Some(IndexValueKind::Uint64);
+
+    // Built-in numeric indices and their reverse-lookup tables. String-based
+    // built-ins (created_by, admin) are already order-preserving.
+    rekey_index(store, IX_CODE_ID, u64_kind)?;
+    rekey_index(store, IX_CREATED_AT, u64_kind)?;
+    rekey_index(store, IX_UPDATED_AT, u64_kind)?;
+    rekey_reverse_map(store, ID_2_CODE_ID, u64_kind)?;
+    rekey_reverse_map(store, ID_2_CREATED_AT, u64_kind)?;
+    rekey_reverse_map(store, ID_2_UPDATED_AT, u64_kind)?;
+
+    // Custom indices, discovered through the per-contract custom index values
+    let keys = CONTRACT_CUSTOM_IX_VALUES
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut index_names: Vec<String> = Vec::with_capacity(4);
+
+    for (_, name) in keys.iter() {
+        if !index_names.contains(name) {
+            index_names.push(name.to_owned());
+        }
+    }
+
+    for name in index_names.iter() {
+        let kind = declared_kinds.get(name).copied();
+        let storage_key = build_index_storage_key(name);
+        let reverse_mapping_storage_key = build_reverse_mapping_storage_key(name);
+        rekey_index(store, Map::new(&storage_key), kind)?;
+        rekey_reverse_map(store, Map::new(&reverse_mapping_storage_key), kind)?;
+    }
+
+    // Only the first value written for a contract used to be recorded in its
+    // custom index values, so they're rebuilt from the re-keyed reverse maps.
+    for (contract_id, name) in keys {
+        let reverse_mapping_storage_key = build_reverse_mapping_storage_key(&name);
+        let reverse_map: Map<ContractId, Vec<u8>> = Map::new(&reverse_mapping_storage_key);
+        match reverse_map.may_load(store, contract_id)? {
+            Some(bytes) => CONTRACT_CUSTOM_IX_VALUES.save(store, (contract_id, &name), &bytes)?,
+            None => CONTRACT_CUSTOM_IX_VALUES.remove(store, (contract_id, &name)),
+        }
+    }
+
+    // Relation edges embed the encoded relation value after the relation name.
+    // Since the original value is stored alongside each edge, we can simply
+    // re-encode it.
+    let relations = IX_REL_CONTRACT_ADDR
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for ((contract_id, edge, rel_addr), value) in relations {
+        if let Some(value) = &value {
+            let mut new_edge = edge[..MAX_SIZEOF_STRING_KEY.min(edge.len())].to_vec();
+            new_edge.extend(value.to_bytes());
+            if new_edge != edge {
+                IX_REL_ADDR.remove(store, (&rel_addr, &edge, contract_id));
+                IX_REL_CONTRACT_ADDR.remove(store, (contract_id, &edge, &rel_addr));
+                IX_REL_ADDR.save(store, (&rel_addr, &new_edge, contract_id), &0)?;
+                IX_REL_CONTRACT_ADDR.save(store, (contract_id, &new_edge, &rel_addr), &Some(value.to_owned()))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Add every custom index already in use to the index registry. Contracts keep
+/// the ability to write to them, as they could before the registry existed.
+fn register_custom_indices(
+    store: &mut dyn Storage,
+    declared_kinds: &HashMap<String, IndexValueKind>,
+) -> Result<(), ContractError> {
+    let entries = CONTRACT_CUSTOM_IX_VALUES
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for ((_, name), bytes) in entries {
+        if CONFIG_CUSTOM_INDICES.has(store, &name) {
+            continue;
+        }
+        let kind = declared_kinds.get(&name).copied().unwrap_or_else(|| infer_kind(&bytes));
+        CONFIG_CUSTOM_INDICES.save(
+            store,
+            &name,
+            &CustomIndex {
+                kind,
+                description: None,
+                writable_by_contracts: true,
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Hiding a contract used to remove it from its custom indices, whereas now
+/// queries filter hidden contracts out. Put these entries back and drop the
+/// explicit "not hidden" flags left over from toggling.
+fn relist_hidden_contracts(store: &mut dyn Storage) -> Result<(), ContractError> {
+    let entries = CONTRACT_ID_2_IS_HIDDEN
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (contract_id, is_hidden) in entries {
+        if !is_hidden {
+            CONTRACT_ID_2_IS_HIDDEN.remove(store, contract_id);
+            continue;
+        }
+        // The reverse map holds the contract's current value for each index
+        let names = CONTRACT_CUSTOM_IX_VALUES
+            .prefix(contract_id)
+            .keys(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for name in names {
+            let storage_key = build_index_storage_key(&name);
+            let reverse_mapping_storage_key = build_reverse_mapping_storage_key(&name);
+            let map: IndexMap = Map::new(&storage_key);
+            let reverse_map: Map<ContractId, Vec<u8>> = Map::new(&reverse_mapping_storage_key);
+            if let Some(bytes) = reverse_map.may_load(store, contract_id)? {
+                map.save(store, (&bytes, contract_id), &0)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Contracts used to be created without the created_at, updated_at,
+/// created_by and admin reverse-lookup entries. Recover them from the indices.
+fn backfill_reverse_maps(store: &mut dyn Storage) -> Result<(), ContractError> {
+    backfill_reverse_map(store, IX_CREATED_AT, ID_2_CREATED_AT)?;
+    backfill_reverse_map(store, IX_CREATED_BY, ID_2_CREATED_BY)?;
+    backfill_reverse_map(store, IX_ADMIN, ID_2_ADMIN)?;
+
+    // The first update of a contract without an updated_at reverse-lookup
+    // entry couldn't remove its creation-time entry from the updated_at index,
+    // so drop any entry that disagrees with the reverse-lookup table first.
+    let keys = IX_UPDATED_AT
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (bytes, contract_id) in keys {
+        if let Some(updated_at_bytes) = ID_2_UPDATED_AT.may_load(store, contract_id)? {
+            if updated_at_bytes != bytes {
+                IX_UPDATED_AT.remove(store, (&bytes, contract_id));
+            }
+        }
+    }
+
+    backfill_reverse_map(store, IX_UPDATED_AT, ID_2_UPDATED_AT)
+}
+
+fn backfill_reverse_map(
+    store: &mut dyn Storage,
+    index: IndexMap,
+    reverse_map: Map<ContractId, Vec<u8>>,
+) -> Result<(), ContractError> {
+    let keys = index
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (bytes, contract_id) in keys {
+        if !reverse_map.has(store, contract_id) {
+            reverse_map.save(store, contract_id, &bytes)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn rekey_index(
+    store: &mut dyn Storage,
+    map: IndexMap,
+    kind: Option<IndexValueKind>,
+) -> Result<(), ContractError> {
+    let keys = map
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (bytes, contract_id) in keys {
+        if let Some(new_bytes) = reencode_legacy(kind, &bytes) {
+            map.remove(store, (&bytes, contract_id));
+            map.save(store, (&new_bytes, contract_id), &0)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn rekey_reverse_map(
+    store: &mut dyn Storage,
+    map: Map<ContractId, Vec<u8>>,
+    kind: Option<IndexValueKind>,
+) -> Result<(), ContractError> {
+    let entries = map
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (contract_id, bytes) in entries {
+        if let Some(new_bytes) = reencode_legacy(kind, &bytes) {
+            map.save(store, contract_id, &new_bytes)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert bytes produced by the legacy encoding into the current encoding.
+/// Returns None if the encoding of the value is unchanged.
+fn reencode_legacy(
+    kind: Option<IndexValueKind>,
+    bytes: &[u8],
+) -> Option<Vec<u8>> {
+    let kind = kind.unwrap_or_else(|| infer_kind(bytes));
+
+    let value = match kind {
+        IndexValueKind::String | IndexValueKind::Bool | IndexValueKind::Uint8 => return None,
+        IndexValueKind::Bytes => IndexValue::Bytes(bytes.to_vec()),
+        IndexValueKind::Binary => IndexValue::Binary(bytes.to_vec().into()),
+        IndexValueKind::Uint128 => IndexValue::Uint128(u128::from_le_bytes(bytes.try_into().ok()?).into()),
+        IndexValueKind::Uint64 => IndexValue::Uint64(u64::from_le_bytes(bytes.try_into().ok()?).into()),
+        IndexValueKind::Uint32 => IndexValue::Uint32(u32::from_le_bytes(bytes.try_into().ok()?)),
+        IndexValueKind::Uint16 => IndexValue::Uint16(u16::from_le_bytes(bytes.try_into().ok()?)),
+        IndexValueKind::Int128 => IndexValue::Int128(i128::from_le_bytes(bytes.try_into().ok()?).into()),
+        IndexValueKind::Int64 => IndexValue::Int64(i64::from_le_bytes(bytes.try_into().ok()?).into()),
+        IndexValueKind::Int32 => IndexValue::Int32(i32::from_le_bytes(bytes.try_into().ok()?)),
+        IndexValueKind::Int16 => IndexValue::Int16(i16::from_le_bytes(bytes.try_into().ok()?)),
+        IndexValueKind::Int8 => IndexValue::Int8(i8::from_le_bytes(bytes.try_into().ok()?)),
+    };
+
+    let new_bytes = value.to_bytes();
+    if new_bytes == bytes {
+        None
+    } else {
+        Some(new_bytes)
+    }
+}
+
+/// Guess the kind of an undeclared custom index value from its byte length.
+/// Integer widths are ambiguous with respect to sign, so assume unsigned.
+fn infer_kind(bytes: &[u8]) -> IndexValueKind {
+    match bytes.len() {
+        MAX_SIZEOF_STRING_KEY => IndexValueKind::String,
+        16 => IndexValueKind::Uint128,
+        8 => IndexValueKind::Uint64,
+        4 => IndexValueKind::Uint32,
+        2 => IndexValueKind::Uint16,
+        1 => IndexValueKind::Uint8,
+        _ => IndexValueKind::Bytes,
+    }
+}
+
+/// Replace the placeholder values of allowed code IDs with empty metadata.
+fn register_allowed_code_ids(store: &mut dyn Storage) -> Result<(), ContractError> {
+    let legacy_allowed_code_ids: Map<u64, u8> = Map::new("allowed_code_ids");
+    let code_ids = legacy_allowed_code_ids
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<u64>>>()?;
+    for code_id in code_ids {
+        CONFIG_ALLOWED_CODE_IDS.save(store, code_id, &CodeIdMetadata::default())?;
+    }
+    Ok(())
+}
+
+/// Sessions used to resume from the last contract ID visited, which is also the
+/// raw key of that contract when scanning every contract in the factory.
+fn rekey_migration_cursors(store: &mut dyn Storage) -> Result<(), ContractError> {
+    #[cw_serde]
+    struct LegacyMigration {
+        params: MigrationParams,
+        status: MigrationStatus,
+        cursor: Option<ContractId>,
+        retry_cursor: Option<ContractId>,
+        n_success: u32,
+        n_error: u32,
+    }
+
+    let legacy_migrations: Map<&String, LegacyMigration> = Map::new("migrations");
+    let entries = legacy_migrations
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (name, legacy) in entries {
+        MIGRATIONS.save(
+            store,
+            &name,
+            &Migration {
+                params: legacy.params,
+                status: legacy.status,
+                cursor: legacy.cursor.map(|id| Binary::from(id.to_be_bytes().to_vec())),
+                retry_cursor: legacy.retry_cursor,
+                n_success: legacy.n_success,
+                n_error: legacy.n_error,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Tags used to be recorded with a weight of 0 whatever their weight in the
+/// weighted tag index, so changing or removing a weighted tag left its old
+/// entry behind. Rebuild the weights from the index, dropping entries of
+/// removed tags and keeping the greatest weight of a tag set more than once.
+fn rebuild_tag_weights(store: &mut dyn Storage) -> Result<(), ContractError> {
+    let keys = IX_WEIGHTED_TAG
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut weights: HashMap<(Vec<u8>, ContractId), u16> = HashMap::with_capacity(keys.len());
+
+    for (tag_bytes, weight, contract_id) in keys {
+        if !IX_TAG.has(store, (&tag_bytes, contract_id)) {
+            IX_WEIGHTED_TAG.remove(store, (&tag_bytes, weight, contract_id));
+            continue;
+        }
+        // Keys are in ascending order of weight for each tag
+        if let Some(prev_weight) = weights.insert((tag_bytes.to_owned(), contract_id), weight) {
+            IX_WEIGHTED_TAG.remove(store, (&tag_bytes, prev_weight, contract_id));
+        }
+    }
+
+    for ((tag_bytes, contract_id), weight) in weights {
+        CONTRACT_TAG_WEIGHTS.save(store, (contract_id, &tag_bytes), &weight)?;
+    }
+
+    Ok(())
+}
+
+/// Start each creator's count of created contracts from the contracts
+/// currently registered as created by it.
+fn count_created_contracts(store: &mut dyn Storage) -> Result<(), ContractError> {
+    let keys = IX_CREATED_BY
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut counts: HashMap<Addr, u32> = HashMap::new();
+
+    for (creator_bytes, _) in keys {
+        if let IndexValue::String(creator) = IndexValue::from_bytes(IndexValueKind::String, &creator_bytes)? {
+            *counts.entry(Addr::unchecked(creator)).or_default() += 1;
+        }
+    }
+
+    for (creator, n_created) in counts {
+        CREATOR_N_CREATED.save(store, &creator, &n_created)?;
+    }
+
+    Ok(())
+}
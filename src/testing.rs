@@ -0,0 +1,110 @@
+//! Helpers shared by unit tests.
+
+use cosmwasm_std::{
+    testing::{mock_env, mock_info},
+    Addr, DepsMut, Uint64,
+};
+
+use crate::{
+    execute::{create::register_contract, update::apply_updates, Context},
+    msg::{IndexUpdate, InstantiateMsg, RelationUpdate, TagUpdate, UpdateOperation},
+    state::{
+        self,
+        models::{Config, SubMsgContext},
+        storage::{ContractId, CONTRACT_ID_COUNTER},
+    },
+};
+
+pub const MANAGER: &str = "manager";
+
+pub fn default_config() -> Config {
+    Config {
+        managed_by: Addr::unchecked(MANAGER),
+        default_code_id: Some(Uint64::new(1)),
+        allowed_code_ids: None,
+        treasury: None,
+        code_id_fees: None,
+        preset_fees: None,
+        creation_limits: None,
+        config_delay: None,
+        pause: None,
+    }
+}
+
+/// Initialize the factory's state as instantiated with the given config.
+pub fn instantiate(
+    deps: DepsMut,
+    config: Config,
+) {
+    state::init(
+        Context {
+            deps,
+            env: mock_env(),
+            info: mock_info(MANAGER, &[]),
+        },
+        InstantiateMsg { config },
+    )
+    .unwrap();
+}
+
+/// Register a contract as if the factory had created it.
+pub fn register(
+    deps: DepsMut,
+    address: &str,
+    created_by: &str,
+    indices: Vec<IndexUpdate>,
+    tags: Vec<TagUpdate>,
+    relations: Vec<RelationUpdate>,
+) -> ContractId {
+    let contract_id = CONTRACT_ID_COUNTER.load(deps.storage).unwrap();
+    CONTRACT_ID_COUNTER.save(deps.storage, &(contract_id + 1)).unwrap();
+
+    let context = SubMsgContext {
+        code_id: Uint64::new(1),
+        contract_id,
+        created_by: Addr::unchecked(created_by),
+        name: None,
+        label: None,
+        admin: None,
+        batch_id: None,
+        indices,
+        relations,
+        tags,
+        callback: false,
+        fee: None,
+        funds: vec![],
+    };
+
+    register_contract(deps.storage, mock_env().block.time, &Addr::unchecked(address), &context).unwrap();
+    apply_updates(
+        deps.storage,
+        contract_id,
+        true,
+        &context.indices,
+        &context.tags,
+        &context.relations,
+    )
+    .unwrap();
+
+    contract_id
+}
+
+pub fn tag(tag: &str) -> TagUpdate {
+    TagUpdate {
+        op: UpdateOperation::Set,
+        tag: tag.to_owned(),
+        weight: None,
+    }
+}
+
+pub fn relation(
+    name: &str,
+    address: &str,
+) -> RelationUpdate {
+    RelationUpdate {
+        op: UpdateOperation::Set,
+        name: name.to_owned(),
+        value: None,
+        address: Addr::unchecked(address),
+    }
+}
@@ -1,23 +1,29 @@
 use base64::{engine::general_purpose::URL_SAFE as BASE64, Engine as _};
-use cosmwasm_std::{ensure_eq, Addr, Binary, StdError, StdResult, Storage};
+use cosmwasm_std::{ensure_eq, Addr, Binary, Order, StdError, StdResult, Storage, Timestamp, Uint64};
 use serde_json::{self, Map, Value};
 
 use crate::{
     error::ContractError,
-    state::storage::{MANAGED_BY, PRESETS},
+    state::{
+        models::{CodeIdMetadata, CreationWindow, CreationWindowLimit, PauseSwitch, Role},
+        storage::{
+            ContractId, CONFIG_ALLOWED_CODE_IDS, CONFIG_CHANNELS, CONFIG_DEFAULT_CODE_ID, CONTRACT_ID_2_IS_HIDDEN,
+            CREATOR_N_CREATED, CREATOR_QUOTA_EXEMPTIONS, CREATOR_WINDOWS, MANAGED_BY, PAUSE_STATE, PRESETS,
+            ROLE_MEMBERS,
+        },
+    },
 };
 
 const DEFAULT_LIMIT: usize = 100;
 const MAX_LIMIT: usize = 500;
+const MAX_SIZEOF_SALT: usize = 64;
 
 pub fn prepare_limit_and_desc(
     limit: Option<u16>,
     desc: Option<bool>,
 ) -> (usize, bool) {
     (
-        limit
-            .and_then(|x| Some((x as usize).clamp(1, MAX_LIMIT)))
-            .unwrap_or(DEFAULT_LIMIT),
+        limit.map(|x| (x as usize).clamp(1, MAX_LIMIT)).unwrap_or(DEFAULT_LIMIT),
         desc.unwrap_or_default(),
     )
 }
@@ -50,6 +56,163 @@ pub fn apply_preset(
     Binary::from_base64(&b64_encoded)
 }
 
+/// Fill in the `{{...}}` placeholders in the strings of a JSON template. A
+/// string made up of a single placeholder is replaced by the resolved value
+/// itself, so that numbers and booleans keep their JSON type.
+pub fn render_template(
+    value: &mut Value,
+    resolve: &mut dyn FnMut(&str) -> Result<Value, ContractError>,
+) -> Result<(), ContractError> {
+    match value {
+        Value::Array(values) => {
+            for x in values.iter_mut() {
+                render_template(x, resolve)?;
+            }
+        },
+        Value::Object(map) => {
+            for (_, x) in map.iter_mut() {
+                render_template(x, resolve)?;
+            }
+        },
+        Value::String(s) if s.contains("{{") => {
+            let mut rendered = String::with_capacity(s.len());
+            let mut rest = s.as_str();
+            while let Some(start) = rest.find("{{") {
+                let end = rest[start..].find("}}").ok_or_else(|| ContractError::ValidationError {
+                    reason: format!("unterminated placeholder in template string: {}", s),
+                })? + start;
+                let resolved = resolve(rest[start + 2..end].trim())?;
+                if start == 0 && end + 2 == s.len() {
+                    *value = resolved;
+                    return Ok(());
+                }
+                rendered.push_str(&rest[..start]);
+                match resolved {
+                    Value::String(x) => rendered.push_str(&x),
+                    Value::Null => {},
+                    x => rendered.push_str(&x.to_string()),
+                }
+                rest = &rest[end + 2..];
+            }
+            rendered.push_str(rest);
+            *s = rendered;
+        },
+        _ => {},
+    }
+    Ok(())
+}
+
+/// Get the code ID to instantiate, given either a code ID or a channel, and
+/// falling back on the default code ID.
+pub fn resolve_code_id(
+    store: &dyn Storage,
+    code_id: Option<Uint64>,
+    channel: Option<&String>,
+) -> Result<Uint64, ContractError> {
+    let code_id = match (code_id, channel) {
+        (Some(_), Some(_)) => {
+            return Err(ContractError::ValidationError {
+                reason: "specify either a code ID or a channel, not both".to_owned(),
+            })
+        },
+        (Some(code_id), None) => code_id,
+        (None, Some(channel)) => resolve_channel(store, channel)?,
+        (None, None) => CONFIG_DEFAULT_CODE_ID
+            .load(store)
+            .map_err(|_| ContractError::ValidationError {
+                reason: "no default code ID set in factory".to_owned(),
+            })?,
+    };
+
+    match CONFIG_ALLOWED_CODE_IDS.may_load(store, code_id.u64())? {
+        Some(metadata) if metadata.deprecated => Err(ContractError::NotAuthorized {
+            reason: format!("code ID is deprecated: {}", code_id.u64()),
+        }),
+        Some(_) => Ok(code_id),
+        None => Err(ContractError::NotAuthorized {
+            reason: format!("not allowed to instantiate code ID: {}", code_id.u64()),
+        }),
+    }
+}
+
+/// Get the code ID to migrate to, given either a code ID or a channel.
+pub fn resolve_migration_code_id(
+    store: &dyn Storage,
+    code_id: Option<Uint64>,
+    channel: Option<&String>,
+) -> Result<Uint64, ContractError> {
+    match (code_id, channel) {
+        (Some(code_id), None) => Ok(code_id),
+        (None, Some(channel)) => resolve_channel(store, channel),
+        _ => Err(ContractError::ValidationError {
+            reason: "specify either a code ID or a channel to migrate to".to_owned(),
+        }),
+    }
+}
+
+pub fn resolve_channel(
+    store: &dyn Storage,
+    channel: &String,
+) -> Result<Uint64, ContractError> {
+    CONFIG_CHANNELS
+        .may_load(store, channel)?
+        .ok_or_else(|| ContractError::ValidationError {
+            reason: format!("channel not found: {}", channel),
+        })
+}
+
+/// Add a code ID to the allowed code IDs, keeping its metadata if already
+/// allowed.
+pub fn allow_code_id(
+    store: &mut dyn Storage,
+    code_id: Uint64,
+) -> StdResult<()> {
+    if !CONFIG_ALLOWED_CODE_IDS.has(store, code_id.u64()) {
+        CONFIG_ALLOWED_CODE_IDS.save(store, code_id.u64(), &CodeIdMetadata::default())?;
+    }
+    Ok(())
+}
+
+/// Ensure that neither the default code ID nor any channel resolves to a code
+/// ID that's about to be disallowed.
+pub fn ensure_code_id_unreferenced(
+    store: &dyn Storage,
+    code_id: Uint64,
+) -> Result<(), ContractError> {
+    if CONFIG_DEFAULT_CODE_ID.may_load(store)? == Some(code_id) {
+        return Err(ContractError::ValidationError {
+            reason: format!("can't disallow the default code ID: {}", code_id),
+        });
+    }
+    ensure_code_id_unused_by_channels(store, code_id)
+}
+
+/// Ensure that no channel resolves to a code ID that's about to be disallowed.
+pub fn ensure_code_id_unused_by_channels(
+    store: &dyn Storage,
+    code_id: Uint64,
+) -> Result<(), ContractError> {
+    for result in CONFIG_CHANNELS.range(store, None, None, Order::Ascending) {
+        let (channel, channel_code_id) = result?;
+        if channel_code_id == code_id {
+            return Err(ContractError::ValidationError {
+                reason: format!("can't disallow code ID {} used by channel: {}", code_id, channel),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Instantiate2 salts must be between 1 and 64 bytes long.
+pub fn validate_salt(salt: &Binary) -> Result<(), ContractError> {
+    if salt.is_empty() || salt.len() > MAX_SIZEOF_SALT {
+        return Err(ContractError::ValidationError {
+            reason: format!("salt must be 1 to {} bytes long", MAX_SIZEOF_SALT),
+        });
+    }
+    Ok(())
+}
+
 pub fn ensure_is_manager(
     store: &dyn Storage,
     addr: &Addr,
@@ -58,12 +221,89 @@ pub fn ensure_is_manager(
         addr,
         MANAGED_BY.load(store)?,
         ContractError::NotAuthorized {
-            reason: "only manager can set presets".to_owned()
+            reason: "only the manager can do this".to_owned()
         }
     );
     Ok(())
 }
 
+/// Ensure that the address holds the given role. The manager implicitly holds
+/// every role.
+pub fn ensure_has_role(
+    store: &dyn Storage,
+    addr: &Addr,
+    role: Role,
+) -> Result<(), ContractError> {
+    if *addr == MANAGED_BY.load(store)? || ROLE_MEMBERS.has(store, (role.as_str(), addr)) {
+        Ok(())
+    } else {
+        Err(ContractError::NotAuthorized {
+            reason: format!("{} role required", role.as_str()),
+        })
+    }
+}
+
+/// Ensure that the given function of the factory isn't paused.
+pub fn ensure_not_paused(
+    store: &dyn Storage,
+    switch: PauseSwitch,
+) -> Result<(), ContractError> {
+    if let Some(pause) = PAUSE_STATE.may_load(store)? {
+        if pause.switches.contains(&switch) {
+            return Err(ContractError::NotAuthorized {
+                reason: format!(
+                    "{:?} is paused: {}",
+                    switch,
+                    pause.reason.unwrap_or_else(|| "no reason given".to_owned())
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Hidden contracts are delisted from contract set queries by default.
+pub fn is_hidden(
+    store: &dyn Storage,
+    contract_id: ContractId,
+) -> StdResult<bool> {
+    Ok(CONTRACT_ID_2_IS_HIDDEN
+        .may_load(store, contract_id)?
+        .unwrap_or_default())
+}
+
+/// Whether the creator is exempt from creation limits.
+pub fn is_exempt(
+    store: &dyn Storage,
+    creator: &Addr,
+) -> Result<bool, ContractError> {
+    Ok(*creator == MANAGED_BY.load(store)? || CREATOR_QUOTA_EXEMPTIONS.has(store, creator))
+}
+
+/// Get the number of contracts the creator has created through the factory.
+pub fn load_n_created(
+    store: &dyn Storage,
+    creator: &Addr,
+) -> Result<u32, ContractError> {
+    Ok(CREATOR_N_CREATED.may_load(store, creator)?.unwrap_or_default())
+}
+
+/// Get the creator's current window, starting a new one if the last one ended.
+pub fn load_window(
+    store: &dyn Storage,
+    time: Timestamp,
+    creator: &Addr,
+    limit: &CreationWindowLimit,
+) -> Result<CreationWindow, ContractError> {
+    Ok(CREATOR_WINDOWS
+        .may_load(store, creator)?
+        .filter(|x| x.started_at.plus_seconds(limit.seconds) > time)
+        .unwrap_or(CreationWindow {
+            started_at: time,
+            n_created: 0,
+        }))
+}
+
 pub fn unpad_vec(bytes: Vec<u8>) -> Vec<u8> {
     let len = bytes.len();
     let mut i = len - 1;